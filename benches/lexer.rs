@@ -0,0 +1,87 @@
+// Times the lexer on a large generated file, and counts what it
+// allocates, which unlike the time doesn't vary from run to run. Run with:
+//
+//     cargo bench --bench lexer
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const NUM_FUNCTIONS: usize = 20_000;
+const NUM_RUNS: u32 = 10;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    // Growing a Vec in place still counts as allocating its new size, the
+    // same as if it had been moved.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// Generates a file with a mix of the tokens we see in real code, including
+// comments and string literals with non-ASCII text.
+fn generate_source() -> String {
+    let mut out = String::new();
+    for i in 0..NUM_FUNCTIONS {
+        let _ = write!(out, concat!(
+            "# Computes the {0}th value, using the naïve approach\n",
+            "fn compute_{0}(x: Int, name: String) -> Int:\n",
+            "    var total = 0\n",
+            "    for j from 0 to x by 2:\n",
+            "        total += j * 0x1F + 3.5e2 - (x % 7)\n",
+            "    if name == \"héllo wörld {0}\" and total >= {0}:\n",
+            "        print(\"日本語\\t{0}\")\n",
+            "    return total\n",
+            "\n",
+        ), i);
+    }
+    out
+}
+
+fn main() {
+    let source = generate_source();
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let num_tokens = impo::lex(&source).expect("generated source should lex").len();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+    let allocated_mb = (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes_before) as f64 / 1_000_000.0;
+
+    let mut best = Duration::MAX;
+    for _ in 0..NUM_RUNS {
+        let start = Instant::now();
+        let tokens = impo::lex(&source).expect("generated source should lex");
+        best = best.min(start.elapsed());
+        assert_eq!(tokens.len(), num_tokens);
+    }
+
+    let megabytes = source.len() as f64 / 1_000_000.0;
+    println!(
+        "lexed {:.1} MB ({} tokens) in {:.1} ms: {:.1} MB/s (best of {} runs)",
+        megabytes,
+        num_tokens,
+        best.as_secs_f64() * 1000.0,
+        megabytes / best.as_secs_f64(),
+        NUM_RUNS,
+    );
+    println!("allocated {allocated_mb:.1} MB in {allocations} allocations");
+}
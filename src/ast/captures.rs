@@ -0,0 +1,61 @@
+use super::exprs::*;
+use super::primitives::Name;
+use super::visit::{NameKind, Scopes, Visitor};
+
+// Finds the free variables of a lambda: the names its body refers to
+// that aren't bound by its params or by declarations inside the body.
+pub fn find_captures(params: &[Name], body: &LambdaBody) -> Vec<Name> {
+    let mut finder = CaptureFinder{
+        scopes: Scopes::default(),
+        captures: Vec::new(),
+    };
+    for param in params {
+        finder.declare(param, NameKind::Value);
+    }
+    match body {
+        LambdaBody::Expr(expr) => finder.visit_expr(expr),
+        LambdaBody::Block(block) => finder.visit_block(block),
+    }
+    finder.captures
+}
+
+struct CaptureFinder {
+    scopes: Scopes,
+    captures: Vec<Name>,
+}
+
+impl CaptureFinder {
+    fn use_name(&mut self, name: &Name) {
+        if self.scopes.lookup(name).is_none() && !self.captures.contains(name) {
+            self.captures.push(name.clone());
+        }
+    }
+}
+
+impl Visitor for CaptureFinder {
+    fn declare(&mut self, name: &Name, kind: NameKind) {
+        self.scopes.declare(name, kind);
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.enter();
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.exit();
+    }
+
+    fn visit_expr(&mut self, expr: &ExprNode) {
+        match expr {
+            ExprNode::Variable(name) => self.use_name(name),
+            // Anything a nested lambda captures is also captured by this
+            // lambda, unless this lambda binds it.
+            ExprNode::Lambda(e) => {
+                for name in &e.captures {
+                    self.use_name(name);
+                }
+            },
+            _ => self.walk_expr(expr),
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use struple::Struple;
+use crate::diagnostics::{ErrorCode, Fix};
 use crate::tokens::Position;
 use crate::values::*;
+use super::captures::find_captures;
 use super::primitives::Name;
+use super::stmts::Block;
+use super::types::{FuncType, TypeNode};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum ExprNode{
@@ -10,16 +14,18 @@ pub enum ExprNode{
     Infix(Box<InfixExpr>),
     Prefix(Box<PrefixExpr>),
     Index(Box<IndexExpr>),
+    TypeApplication(Box<TypeApplicationExpr>),
     Range(Box<RangeExpr>),
     FieldLookup(Box<FieldLookupExpr>),
     TupleLookup(Box<TupleLookupExpr>),
     Variable(Name),
     Array(Box<ArrayExpr>),
     Tuple(Box<TupleExpr>),
-    StringLiteral(Box<String>),
+    StringLiteral(Box<StringLiteral>),
     IntLiteral(Box<IntLiteral>),
     FloatLiteral(Box<FloatLiteral>),
     BoolLiteral(bool),
+    Lambda(Box<LambdaExpr>),
     Error(Box<ErrorExpr>),
 }
 
@@ -219,6 +225,20 @@ impl From<IndexExpr> for ExprNode {
     }
 }
 
+// Explicitly instantiates a generic function or class. For example,
+// `Array[T](8)` is a call whose func is the type application `Array[T]`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct TypeApplicationExpr {
+    pub source: ExprNode,
+    pub type_args: Vec<TypeNode>,
+}
+
+impl From<TypeApplicationExpr> for ExprNode {
+    fn from(other: TypeApplicationExpr) -> ExprNode {
+        ExprNode::TypeApplication(Box::new(other))
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct FieldLookupExpr {
     pub source: ExprNode,
@@ -285,15 +305,65 @@ impl From<TupleExpr> for ExprNode {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct LambdaExpr {
+    pub param_names: Vec<Name>,
+    pub signature: FuncType,
+    pub body: LambdaBody,
+    // Every name the body uses but doesn't itself bind, in order of first
+    // use. This is purely syntactic, so it also includes globals and
+    // builtins: later passes are expected to filter those out.
+    pub captures: Vec<Name>,
+}
+
+impl LambdaExpr {
+    #[must_use]
+    pub fn new(param_names: Vec<Name>, signature: FuncType, body: LambdaBody) -> LambdaExpr {
+        let captures = find_captures(&param_names, &body);
+        LambdaExpr{
+            param_names: param_names,
+            signature: signature,
+            body: body,
+            captures: captures,
+        }
+    }
+}
+
+impl From<LambdaExpr> for ExprNode {
+    fn from(other: LambdaExpr) -> ExprNode {
+        ExprNode::Lambda(Box::new(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum LambdaBody {
+    Expr(ExprNode),
+    Block(Block),
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ErrorExpr{
+    pub code: ErrorCode,
     pub message: String,
     pub span: (Position, Position),
+    pub fix: Option<Box<Fix>>,
+}
+
+impl From<ErrorExpr> for ExprNode {
+    fn from(other: ErrorExpr) -> ExprNode {
+        ExprNode::Error(Box::new(other))
+    }
 }
 
 impl<S: AsRef<str>> From<S> for ExprNode {
     fn from(other: S) -> ExprNode {
-        ExprNode::StringLiteral(Box::new(other.as_ref().to_owned()))
+        ExprNode::StringLiteral(Box::new(other.as_ref().into()))
+    }
+}
+
+impl From<StringLiteral> for ExprNode {
+    fn from(other: StringLiteral) -> ExprNode {
+        ExprNode::StringLiteral(Box::new(other))
     }
 }
 
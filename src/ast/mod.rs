@@ -2,8 +2,12 @@ pub mod primitives;
 pub mod exprs;
 pub mod types;
 pub mod stmts;
+pub mod visit;
+mod captures;
+mod type_args;
 
 pub use self::primitives::*;
 pub use self::exprs::*;
 pub use self::types::*;
-pub use self::stmts::*;
\ No newline at end of file
+pub use self::stmts::*;
+pub use self::type_args::resolve_type_args;
\ No newline at end of file
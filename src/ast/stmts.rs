@@ -1,7 +1,8 @@
 use struple::Struple;
-use crate::ast::types::FuncType;
-use super::exprs::ExprNode;
+use crate::ast::types::{FuncType, TypeNode};
+use super::exprs::{ErrorExpr, ExprNode, InfixOp};
 use super::primitives::{Identifier, Name};
+use crate::tokens::Position;
 
 pub type Block = Vec<StmtNode>;
 
@@ -16,19 +17,26 @@ pub enum StmtNode{
     InterfaceDef(Box<InterfaceDefStmt>),
     ClassDef(Box<ClassDefStmt>),
     SentinalDef(Box<SentinalDefStmt>),
+    ConstDef(Box<ConstDefStmt>),
     FieldSignatureDef(Box<FieldSignatureDefStmt>),
     FuncSignatureDef(Box<FuncSignatureDefStmt>),
     FuncImplementationDef(Box<FuncImplementationDefStmt>),
 
     // Control flow
     If(Box<IfStmt>),
+    Match(Box<MatchStmt>),
     For(Box<ForStmt>),
     Foreach(Box<ForeachStmt>),
     While(Box<WhileStmt>),
+    Break(Box<BreakStmt>),
+    Continue(Box<ContinueStmt>),
     Return(Box<ReturnStmt>),
     Panic(Box<PanicStmt>),
+    Try(Box<TryStmt>),
+    Throw(Box<ThrowStmt>),
 
     // Line-based primitives
+    VarDecl(Box<VarDeclStmt>),
     Assignment(Box<AssignmentStmt>),
     Line(Box<LineStmt>),
     EmptyLine(),
@@ -62,11 +70,22 @@ impl Comment {
     }
 }
 
+// A hint of the form `!Target: Name(arg1, arg2, ...)`. Hints are ignored
+// by the language itself, but are attached to the following statement
+// for use by the transpiler for the given target.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct Hint {
+    pub target: Name,
+    pub name: Name,
+    pub args: Vec<ExprNode>,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ImportStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub source: Identifier,
-    pub imports: Vec<Name>,
+    pub imports: Vec<ImportedName>,
 }
 
 impl From<ImportStmt> for StmtNode {
@@ -75,12 +94,30 @@ impl From<ImportStmt> for StmtNode {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct ImportedName {
+    pub name: Name,
+    pub alias: Option<Name>,
+}
+
+impl ImportedName {
+    // The name the import is bound to within the importing module
+    #[must_use]
+    pub fn local_name(&self) -> &Name {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct InterfaceDefStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub identifier: Identifier,
+    pub typevars: Vec<Name>,
     pub fields: Vec<FieldSignatureDefStmt>,
     pub functions: Vec<FuncSignatureDefStmt>,
+    // Members that couldn't be parsed
+    pub errors: Vec<ErrorExpr>,
 }
 
 impl From<InterfaceDefStmt> for StmtNode {
@@ -92,10 +129,17 @@ impl From<InterfaceDefStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ClassDefStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub identifier: Identifier,
-    pub implements: Option<Identifier>,
+    pub typevars: Vec<Name>,
+    // Each entry is expected to be a ReferenceType naming an interface
+    pub implements: Vec<TypeNode>,
+    pub constants: Vec<ConstDefStmt>,
     pub fields: Vec<FieldSignatureDefStmt>,
+    pub constructor: Option<FuncImplementationDefStmt>,
     pub functions: Vec<FuncImplementationDefStmt>,
+    // Members that couldn't be parsed
+    pub errors: Vec<ErrorExpr>,
 }
 
 impl From<ClassDefStmt> for StmtNode {
@@ -107,6 +151,7 @@ impl From<ClassDefStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct SentinalDefStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub identifier: Identifier,
 }
 
@@ -116,10 +161,28 @@ impl From<SentinalDefStmt> for StmtNode {
     }
 }
 
+// Consts may only be declared at module or class scope.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct ConstDefStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    pub name: Name,
+    pub typ: Option<TypeNode>,
+    pub value: ExprNode,
+}
+
+impl From<ConstDefStmt> for StmtNode {
+    fn from(other: ConstDefStmt) -> StmtNode {
+        StmtNode::ConstDef(Box::new(other))
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct FieldSignatureDefStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub identifier: Identifier,
+    pub typ: TypeNode,
 }
 
 impl From<FieldSignatureDefStmt> for StmtNode {
@@ -131,6 +194,7 @@ impl From<FieldSignatureDefStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct FuncSignatureDefStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     // TODO: Make this an AbsoluteIdentifier instead?
     pub name: Name,
     pub signature: FuncType,
@@ -158,6 +222,7 @@ impl From<FuncImplementationDefStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct IfStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub if_branch: (ExprNode, Block),
     pub elif_branches: Vec<(ExprNode, Block)>,
     pub else_branch: Option<Block>,
@@ -169,15 +234,54 @@ impl From<IfStmt> for StmtNode {
     }
 }
 
+// Runs the first case whose type the value has. A case for a sentinal
+// is written using the sentinal's type, which has only one value.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct MatchStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    pub value: ExprNode,
+    pub cases: Vec<MatchCase>,
+    pub else_case: Option<Block>,
+}
+
+impl From<MatchStmt> for StmtNode {
+    fn from(other: MatchStmt) -> StmtNode {
+        StmtNode::Match(Box::new(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct MatchCase {
+    pub comment: Comment,
+    pub typ: TypeNode,
+    // The value, narrowed to the case's type
+    pub name: Option<Name>,
+    pub body: Block,
+}
+
+// Counts from start (inclusive) to end. Counting up stops before the end,
+// but counting down includes it, so `from n - 1 downto 0` visits the same
+// values as `from 0 to n` in reverse. The step, if present, is always a
+// positive amount.
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ForStmt{
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub variable: Name,
-    pub start: i64,
-    pub end: i64,
+    pub start: ExprNode,
+    pub direction: ForDirection,
+    pub end: ExprNode,
+    pub step: Option<ExprNode>,
     pub body: Block,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ForDirection {
+    Ascending,
+    Descending,
+}
+
 impl From<ForStmt> for StmtNode {
     fn from(other: ForStmt) -> StmtNode {
         StmtNode::For(Box::new(other))
@@ -187,7 +291,8 @@ impl From<ForStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ForeachStmt {
     pub comment: Comment,
-    pub variables: Vec<Name>,
+    pub hints: Vec<Hint>,
+    pub variables: Vec<VarPattern>,
     pub iterable: ExprNode,
     pub body: Block,
 }
@@ -201,6 +306,7 @@ impl From<ForeachStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct WhileStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub cond: ExprNode,
     pub body: Block,
 }
@@ -211,9 +317,38 @@ impl From<WhileStmt> for StmtNode {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct BreakStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    // The span of the keyword, for reporting errors
+    pub span: (Position, Position),
+}
+
+impl From<BreakStmt> for StmtNode {
+    fn from(other: BreakStmt) -> StmtNode {
+        StmtNode::Break(Box::new(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct ContinueStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    // The span of the keyword, for reporting errors
+    pub span: (Position, Position),
+}
+
+impl From<ContinueStmt> for StmtNode {
+    fn from(other: ContinueStmt) -> StmtNode {
+        StmtNode::Continue(Box::new(other))
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct ReturnStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub value: Option<ExprNode>,
 }
 
@@ -226,6 +361,7 @@ impl From<ReturnStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct PanicStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub value: ExprNode,
 }
 
@@ -235,10 +371,88 @@ impl From<PanicStmt> for StmtNode {
     }
 }
 
+// Runs the body, handling errors thrown from within it using the first
+// catch clause whose type matches. Errors that match no clause continue
+// propagating to the caller.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct TryStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    pub body: Block,
+    pub catch_clauses: Vec<CatchClause>,
+}
+
+impl From<TryStmt> for StmtNode {
+    fn from(other: TryStmt) -> StmtNode {
+        StmtNode::Try(Box::new(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct CatchClause {
+    pub typ: TypeNode,
+    pub name: Option<Name>,
+    pub body: Block,
+}
+
+// Unlike panics, thrown errors are recoverable: the thrown value must
+// match one of the types in the enclosing function's throws clause
+// unless it's caught by an enclosing try statement.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct ThrowStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    pub value: ExprNode,
+}
+
+impl From<ThrowStmt> for StmtNode {
+    fn from(other: ThrowStmt) -> StmtNode {
+        StmtNode::Throw(Box::new(other))
+    }
+}
+
+// Declares one or more new variables in the current block. Unlike
+// assignments, declarations must always have an initial value.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
+pub struct VarDeclStmt {
+    pub comment: Comment,
+    pub hints: Vec<Hint>,
+    pub target: VarPattern,
+    pub typ: Option<TypeNode>,
+    pub value: ExprNode,
+}
+
+impl From<VarDeclStmt> for StmtNode {
+    fn from(other: VarDeclStmt) -> StmtNode {
+        StmtNode::VarDecl(Box::new(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum VarPattern {
+    Name(Name),
+    Tuple(Vec<VarPattern>),
+}
+
+impl VarPattern {
+    // Returns every name bound by this pattern, from left to right
+    #[must_use]
+    pub fn names(&self) -> Vec<&Name> {
+        match self {
+            VarPattern::Name(name) => vec![name],
+            VarPattern::Tuple(items) => items.iter().flat_map(VarPattern::names).collect(),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct AssignmentStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub target: ExprNode,
+    // Set for compound assignments such as `a += b`, which are
+    // semantically equivalent to `a = a + b`
+    pub op: Option<InfixOp>,
     pub value: ExprNode,
 }
 
@@ -251,6 +465,7 @@ impl From<AssignmentStmt> for StmtNode {
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Struple)]
 pub struct LineStmt {
     pub comment: Comment,
+    pub hints: Vec<Hint>,
     pub expr: ExprNode,
 }
 
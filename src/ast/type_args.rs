@@ -0,0 +1,80 @@
+use super::captures::find_captures;
+use super::exprs::*;
+use super::primitives::Name;
+use super::stmts::*;
+use super::types::TypeNode;
+use super::visit::{NameKind, Scopes, VisitorMut};
+
+// The parser can't tell whether `table[MAX_SIZE](x)` indexes `table` or
+// passes it a type arg, so it always produces a type application. This
+// turns each type application back into an index if its one type arg is
+// a plain name, like `MAX_SIZE` or `a.b`, whose first part refers to a
+// value declared earlier in an enclosing scope.
+pub fn resolve_type_args(program: &mut Program) {
+    let mut resolver = TypeArgResolver{
+        scopes: Scopes::default(),
+    };
+    resolver.visit_block(&mut program.body);
+}
+
+struct TypeArgResolver {
+    scopes: Scopes,
+}
+
+impl TypeArgResolver {
+    fn as_index(&self, type_args: &[TypeNode]) -> Option<ExprNode> {
+        let [TypeNode::Reference(typ)] = type_args else {
+            return None;
+        };
+        if !typ.type_params.is_empty() {
+            return None;
+        }
+        let identifier = &typ.identifier;
+        let mut names = identifier.parent.iter().chain([&identifier.name]);
+        let first = names.next()?;
+        if self.scopes.lookup(first) != Some(NameKind::Value) {
+            return None;
+        }
+        let name_chain: Vec<Name> = names.cloned().collect();
+        Some(if name_chain.is_empty() {
+            ExprNode::Variable(first.clone())
+        } else {
+            FieldLookupExpr{
+                source: ExprNode::Variable(first.clone()),
+                name_chain: name_chain,
+            }.into()
+        })
+    }
+}
+
+impl VisitorMut for TypeArgResolver {
+    fn declare(&mut self, name: &Name, kind: NameKind) {
+        self.scopes.declare(name, kind);
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.enter();
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.exit();
+    }
+
+    fn visit_expr(&mut self, expr: &mut ExprNode) {
+        self.walk_expr(expr);
+        match expr {
+            ExprNode::TypeApplication(e) => {
+                if let Some(index) = self.as_index(&e.type_args) {
+                    *expr = IndexExpr{
+                        source: e.source.clone(),
+                        index: index,
+                    }.into();
+                }
+            },
+            // The captures were found before any type applications in the
+            // body were turned into indexes, so they need to be redone.
+            ExprNode::Lambda(e) => e.captures = find_captures(&e.param_names, &e.body),
+            _ => {},
+        }
+    }
+}
@@ -2,6 +2,7 @@ use derive_more::Constructor;
 use struple::Struple;
 
 use super::primitives::{Name, Identifier};
+use crate::diagnostics::{ErrorCode, Fix};
 use crate::tokens::Position;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -32,6 +33,9 @@ pub struct FuncType {
     pub typevars: Vec<Name>,
     pub param_types: Vec<TypeNode>,
     pub return_type: TypeNode,
+    // The error types this function may throw, in addition to returning
+    // normally. Empty if the function cannot throw.
+    pub throws: Vec<TypeNode>,
 }
 
 impl From<FuncType> for TypeNode {
@@ -64,8 +68,10 @@ impl From<TupleType> for TypeNode {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Constructor, Struple)]
 pub struct ErrorType {
+    pub code: ErrorCode,
     pub message: String,
     pub span: (Position, Position),
+    pub fix: Option<Box<Fix>>,
 }
 
 impl From<ErrorType> for TypeNode {
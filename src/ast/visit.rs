@@ -0,0 +1,336 @@
+use super::exprs::*;
+use super::primitives::Name;
+use super::stmts::*;
+use super::types::*;
+
+// Passes over the AST implement 'Visitor', or 'VisitorMut' if they change
+// it, and override the 'visit_*' methods for the nodes they care about.
+// Each 'walk_*' method visits the children of a node, so an override can
+// call it to carry on into them.
+//
+// The walk also reports which names each statement declares, and where
+// scopes start and end, for passes that need to know what a name refers
+// to. See 'Scopes'.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NameKind {
+    Value,
+    Type,
+}
+
+macro_rules! define_visitor {
+    ($visitor:ident $(, $mutability:ident)?) => {
+        pub trait $visitor {
+            fn declare(&mut self, _name: &Name, _kind: NameKind) {}
+
+            fn enter_scope(&mut self) {}
+
+            fn exit_scope(&mut self) {}
+
+            fn visit_block(&mut self, block: & $($mutability)? [StmtNode]) {
+                self.walk_scope([], block);
+            }
+
+            // Visits a block where the given names are bound only within
+            // the block, such as loop variables.
+            fn walk_scope<'n>(&mut self, names: impl IntoIterator<Item = &'n Name>, block: & $($mutability)? [StmtNode]) {
+                self.enter_scope();
+                for name in names {
+                    self.declare(name, NameKind::Value);
+                }
+                for stmt in block {
+                    self.visit_stmt(stmt);
+                }
+                self.exit_scope();
+            }
+
+            fn visit_function(&mut self, function: & $($mutability)? FuncImplementationDefStmt) {
+                self.walk_function(function);
+            }
+
+            fn walk_function(&mut self, function: & $($mutability)? FuncImplementationDefStmt) {
+                let signature = & $($mutability)? function.function.signature;
+                self.visit_func_type(signature);
+                self.enter_scope();
+                for typevar in &signature.typevars {
+                    self.declare(typevar, NameKind::Type);
+                }
+                self.walk_scope(&function.function.param_names, & $($mutability)? function.body);
+                self.exit_scope();
+            }
+
+            // Errors in place of class or interface members that couldn't
+            // be parsed.
+            fn visit_member_error(&mut self, _error: & $($mutability)? ErrorExpr) {}
+
+            fn visit_stmt(&mut self, stmt: & $($mutability)? StmtNode) {
+                self.walk_stmt(stmt);
+            }
+
+            #[allow(clippy::too_many_lines)]
+            fn walk_stmt(&mut self, stmt: & $($mutability)? StmtNode) {
+                match stmt {
+                    StmtNode::Program(s) => self.visit_block(& $($mutability)? s.body),
+                    StmtNode::Import(s) => {
+                        for import in &s.imports {
+                            self.declare(import.local_name(), NameKind::Value);
+                        }
+                    },
+                    StmtNode::InterfaceDef(s) => {
+                        self.declare(&s.identifier.name, NameKind::Type);
+                        self.enter_scope();
+                        for typevar in &s.typevars {
+                            self.declare(typevar, NameKind::Type);
+                        }
+                        for field in & $($mutability)? s.fields {
+                            self.visit_type(& $($mutability)? field.typ);
+                        }
+                        for function in & $($mutability)? s.functions {
+                            self.visit_func_type(& $($mutability)? function.signature);
+                        }
+                        for error in & $($mutability)? s.errors {
+                            self.visit_member_error(error);
+                        }
+                        self.exit_scope();
+                    },
+                    StmtNode::ClassDef(s) => {
+                        self.declare(&s.identifier.name, NameKind::Type);
+                        self.enter_scope();
+                        for typevar in &s.typevars {
+                            self.declare(typevar, NameKind::Type);
+                        }
+                        self.declare(&"this".into(), NameKind::Value);
+                        self.visit_types(& $($mutability)? s.implements);
+                        for constant in & $($mutability)? s.constants {
+                            self.visit_const(constant);
+                            self.declare(&constant.name, NameKind::Value);
+                        }
+                        for field in & $($mutability)? s.fields {
+                            self.visit_type(& $($mutability)? field.typ);
+                        }
+                        if let Some(constructor) = & $($mutability)? s.constructor {
+                            self.visit_function(constructor);
+                        }
+                        for function in & $($mutability)? s.functions {
+                            self.visit_function(function);
+                        }
+                        for error in & $($mutability)? s.errors {
+                            self.visit_member_error(error);
+                        }
+                        self.exit_scope();
+                    },
+                    StmtNode::SentinalDef(s) => self.declare(&s.identifier.name, NameKind::Type),
+                    StmtNode::ConstDef(s) => {
+                        self.visit_const(s);
+                        self.declare(&s.name, NameKind::Value);
+                    },
+                    StmtNode::FieldSignatureDef(s) => self.visit_type(& $($mutability)? s.typ),
+                    StmtNode::FuncSignatureDef(s) => self.visit_func_type(& $($mutability)? s.signature),
+                    StmtNode::FuncImplementationDef(s) => {
+                        // Declared first, so functions can call themselves
+                        self.declare(&s.function.name, NameKind::Value);
+                        self.visit_function(s);
+                    },
+                    StmtNode::If(s) => {
+                        self.visit_expr(& $($mutability)? s.if_branch.0);
+                        self.visit_block(& $($mutability)? s.if_branch.1);
+                        for (cond, body) in & $($mutability)? s.elif_branches {
+                            self.visit_expr(cond);
+                            self.visit_block(body);
+                        }
+                        if let Some(body) = & $($mutability)? s.else_branch {
+                            self.visit_block(body);
+                        }
+                    },
+                    StmtNode::Match(s) => {
+                        self.visit_expr(& $($mutability)? s.value);
+                        for case in & $($mutability)? s.cases {
+                            self.visit_type(& $($mutability)? case.typ);
+                            self.walk_scope(&case.name, & $($mutability)? case.body);
+                        }
+                        if let Some(body) = & $($mutability)? s.else_case {
+                            self.visit_block(body);
+                        }
+                    },
+                    StmtNode::For(s) => {
+                        self.visit_expr(& $($mutability)? s.start);
+                        self.visit_expr(& $($mutability)? s.end);
+                        if let Some(step) = & $($mutability)? s.step {
+                            self.visit_expr(step);
+                        }
+                        self.walk_scope([&s.variable], & $($mutability)? s.body);
+                    },
+                    StmtNode::Foreach(s) => {
+                        self.visit_expr(& $($mutability)? s.iterable);
+                        self.walk_scope(s.variables.iter().flat_map(VarPattern::names), & $($mutability)? s.body);
+                    },
+                    StmtNode::While(s) => {
+                        self.visit_expr(& $($mutability)? s.cond);
+                        self.visit_block(& $($mutability)? s.body);
+                    },
+                    StmtNode::Try(s) => {
+                        self.visit_block(& $($mutability)? s.body);
+                        for clause in & $($mutability)? s.catch_clauses {
+                            self.visit_type(& $($mutability)? clause.typ);
+                            self.walk_scope(&clause.name, & $($mutability)? clause.body);
+                        }
+                    },
+                    StmtNode::Return(s) => {
+                        if let Some(value) = & $($mutability)? s.value {
+                            self.visit_expr(value);
+                        }
+                    },
+                    StmtNode::Panic(s) => self.visit_expr(& $($mutability)? s.value),
+                    StmtNode::Throw(s) => self.visit_expr(& $($mutability)? s.value),
+                    StmtNode::VarDecl(s) => {
+                        if let Some(typ) = & $($mutability)? s.typ {
+                            self.visit_type(typ);
+                        }
+                        self.visit_expr(& $($mutability)? s.value);
+                        for name in s.target.names() {
+                            self.declare(name, NameKind::Value);
+                        }
+                    },
+                    StmtNode::Assignment(s) => {
+                        self.visit_expr(& $($mutability)? s.target);
+                        self.visit_expr(& $($mutability)? s.value);
+                    },
+                    StmtNode::Line(s) => self.visit_expr(& $($mutability)? s.expr),
+                    StmtNode::Break(_)
+                    | StmtNode::Continue(_)
+                    | StmtNode::EmptyLine() => {},
+                }
+            }
+
+            fn visit_const(&mut self, constant: & $($mutability)? ConstDefStmt) {
+                if let Some(typ) = & $($mutability)? constant.typ {
+                    self.visit_type(typ);
+                }
+                self.visit_expr(& $($mutability)? constant.value);
+            }
+
+            fn visit_exprs(&mut self, exprs: & $($mutability)? [ExprNode]) {
+                for expr in exprs {
+                    self.visit_expr(expr);
+                }
+            }
+
+            fn visit_expr(&mut self, expr: & $($mutability)? ExprNode) {
+                self.walk_expr(expr);
+            }
+
+            fn walk_expr(&mut self, expr: & $($mutability)? ExprNode) {
+                match expr {
+                    ExprNode::FuncCall(e) => {
+                        self.visit_expr(& $($mutability)? e.func);
+                        self.visit_exprs(& $($mutability)? e.params);
+                    },
+                    ExprNode::ExplicitParenthesis(e) => self.visit_expr(e),
+                    ExprNode::Infix(e) => self.visit_exprs(& $($mutability)? e.exprs),
+                    ExprNode::Prefix(e) => self.visit_expr(& $($mutability)? e.expr),
+                    ExprNode::Index(e) => {
+                        self.visit_expr(& $($mutability)? e.source);
+                        self.visit_expr(& $($mutability)? e.index);
+                    },
+                    ExprNode::TypeApplication(e) => {
+                        self.visit_expr(& $($mutability)? e.source);
+                        self.visit_types(& $($mutability)? e.type_args);
+                    },
+                    ExprNode::Range(e) => {
+                        self.visit_expr(& $($mutability)? e.start);
+                        self.visit_expr(& $($mutability)? e.end);
+                    },
+                    ExprNode::FieldLookup(e) => self.visit_expr(& $($mutability)? e.source),
+                    ExprNode::TupleLookup(e) => self.visit_expr(& $($mutability)? e.source),
+                    ExprNode::Array(e) => self.visit_exprs(& $($mutability)? e.items),
+                    ExprNode::Tuple(e) => self.visit_exprs(& $($mutability)? e.items),
+                    ExprNode::Lambda(e) => {
+                        self.visit_func_type(& $($mutability)? e.signature);
+                        self.enter_scope();
+                        for typevar in &e.signature.typevars {
+                            self.declare(typevar, NameKind::Type);
+                        }
+                        match & $($mutability)? e.body {
+                            LambdaBody::Expr(body) => {
+                                for name in &e.param_names {
+                                    self.declare(name, NameKind::Value);
+                                }
+                                self.visit_expr(body);
+                            },
+                            LambdaBody::Block(body) => self.walk_scope(&e.param_names, body),
+                        }
+                        self.exit_scope();
+                    },
+                    ExprNode::Variable(_)
+                    | ExprNode::StringLiteral(_)
+                    | ExprNode::IntLiteral(_)
+                    | ExprNode::FloatLiteral(_)
+                    | ExprNode::BoolLiteral(_)
+                    | ExprNode::Error(_) => {},
+                }
+            }
+
+            fn visit_types(&mut self, types: & $($mutability)? [TypeNode]) {
+                for typ in types {
+                    self.visit_type(typ);
+                }
+            }
+
+            fn visit_func_type(&mut self, func: & $($mutability)? FuncType) {
+                self.visit_types(& $($mutability)? func.param_types);
+                self.visit_type(& $($mutability)? func.return_type);
+                self.visit_types(& $($mutability)? func.throws);
+            }
+
+            fn visit_type(&mut self, typ: & $($mutability)? TypeNode) {
+                self.walk_type(typ);
+            }
+
+            fn walk_type(&mut self, typ: & $($mutability)? TypeNode) {
+                match typ {
+                    TypeNode::Reference(t) => self.visit_types(& $($mutability)? t.type_params),
+                    TypeNode::Func(t) => self.visit_func_type(t),
+                    TypeNode::Union(t) => self.visit_types(& $($mutability)? t.variants),
+                    TypeNode::Tuple(t) => self.visit_types(& $($mutability)? t.items),
+                    TypeNode::Unit | TypeNode::Empty | TypeNode::Error(_) => {},
+                }
+            }
+        }
+    };
+}
+
+define_visitor!(Visitor);
+define_visitor!(VisitorMut, mut);
+
+// Tracks the names in scope for a visitor, as reported by the walk.
+#[derive(Default)]
+pub struct Scopes {
+    // With the innermost last
+    names: Vec<(Name, NameKind)>,
+    starts: Vec<usize>,
+}
+
+impl Scopes {
+    pub fn declare(&mut self, name: &Name, kind: NameKind) {
+        self.names.push((name.clone(), kind));
+    }
+
+    pub fn enter(&mut self) {
+        self.starts.push(self.names.len());
+    }
+
+    pub fn exit(&mut self) {
+        if let Some(start) = self.starts.pop() {
+            self.names.truncate(start);
+        }
+    }
+
+    // What the given name refers to, if it's in scope
+    #[must_use]
+    pub fn lookup(&self, name: &Name) -> Option<NameKind> {
+        self.names.iter()
+            .rev()
+            .find(|(declared, _)| declared == name)
+            .map(|(_, kind)| *kind)
+    }
+}
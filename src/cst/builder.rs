@@ -0,0 +1,117 @@
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use super::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, Trivia, TriviaKind};
+use crate::tokens::{Position, Token, TokenKind};
+
+// Groups tokens into lines and blocks, and gives each token the text
+// between it and the token before it as trivia.
+pub struct Builder<'a> {
+    source: &'a str,
+    tokens: Peekable<IntoIter<Token>>,
+    // The byte offset of the first char that isn't in the tree yet. Tokens
+    // the lexer makes up can come before this.
+    cursor: usize,
+    // The end of the last token that moved the cursor.
+    last_end: Position,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(source: &'a str, tokens: Vec<Token>) -> Builder<'a> {
+        Builder{
+            source: source,
+            tokens: tokens.into_iter().peekable(),
+            cursor: 0,
+            last_end: Position::start(),
+        }
+    }
+
+    pub fn build(mut self) -> SyntaxNode {
+        let mut children = self.items(false);
+
+        // Anything after the last token becomes trivia of the end of file
+        let mut end = self.last_end;
+        for c in self.source.get(self.cursor..).unwrap_or_default().chars() {
+            end.advance(c);
+        }
+        children.push(self.token(Token{kind: TokenKind::EndOfFile, position: end, end: end}));
+
+        SyntaxNode{kind: SyntaxKind::File, children}
+    }
+
+    // Lines and blocks up to the end of the current block, or of the file.
+    fn items(&mut self, in_block: bool) -> Vec<SyntaxElement> {
+        let mut items = Vec::new();
+        while let Some(next) = self.tokens.peek() {
+            let node = match next.kind {
+                TokenKind::Unindent if in_block => break,
+                TokenKind::Indent => self.block(),
+                _ => self.line(),
+            };
+            items.push(SyntaxElement::Node(node));
+        }
+        items
+    }
+
+    fn block(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        if let Some(indent) = self.tokens.next() {
+            children.push(self.token(indent));
+        }
+        children.extend(self.items(true));
+        if let Some(unindent) = self.tokens.next_if(|token| token.kind == TokenKind::Unindent) {
+            children.push(self.token(unindent));
+        }
+        SyntaxNode{kind: SyntaxKind::Block, children}
+    }
+
+    // A line ends after its newline, or where a block starts or ends if
+    // the lexer didn't find one. It always has at least one token.
+    fn line(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        while let Some(next) = self.tokens.peek() {
+            let is_block_edge = matches!(next.kind, TokenKind::Indent | TokenKind::Unindent);
+            if is_block_edge && !children.is_empty() {
+                break;
+            }
+            let is_newline = next.kind == TokenKind::Newline;
+            if let Some(token) = self.tokens.next() {
+                children.push(self.token(token));
+            }
+            if is_newline {
+                break;
+            }
+        }
+        SyntaxNode{kind: SyntaxKind::Line, children}
+    }
+
+    fn token(&mut self, token: Token) -> SyntaxElement {
+        let start = token.position.offset.max(self.cursor);
+        let end = token.end.offset.max(start);
+        let leading_trivia = split_trivia(self.source.get(self.cursor..start).unwrap_or_default());
+        let text = self.source.get(start..end).unwrap_or_default().to_owned();
+        self.cursor = end;
+        if token.end.offset == end {
+            self.last_end = token.end;
+        }
+        SyntaxElement::Token(SyntaxToken{token, leading_trivia, text})
+    }
+}
+
+// The text between tokens is only ever whitespace, so we split it into
+// runs of newlines and runs of everything else.
+fn split_trivia(text: &str) -> Vec<Trivia> {
+    let mut out: Vec<Trivia> = Vec::new();
+    for c in text.chars() {
+        let kind = if c == '\n' || c == '\r' {
+            TriviaKind::LineBreak
+        } else {
+            TriviaKind::Whitespace
+        };
+        match out.last_mut() {
+            Some(last) if last.kind == kind => last.text.push(c),
+            _ => out.push(Trivia{kind, text: c.to_string()}),
+        }
+    }
+    out
+}
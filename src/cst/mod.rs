@@ -0,0 +1,200 @@
+mod builder;
+
+use std::fmt;
+
+use crate::ast::Program;
+use crate::lexer::{lex_with_recovery, LexerError};
+use crate::parser::{parse, ParserError};
+use crate::tokens::{Token, TokenKind};
+
+use self::builder::Builder;
+
+// A lossless syntax tree: printing it gives back the source code byte for
+// byte, including the whitespace, blank lines and comments the AST drops.
+//
+// The tree only knows about the layout of the source code. A file is a
+// list of lines and indented blocks, and each line holds the tokens up to
+// and including its newline. Use 'to_ast' to parse the tokens into an AST.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum SyntaxKind {
+    // The whole file. The last child is always the end of file token,
+    // which holds any whitespace after the last line.
+    File,
+    // Starts with an indent token, and ends with an unindent token.
+    Block,
+    Line,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+// A token along with the whitespace before it. 'text' is exactly how the
+// token is written in the source code; it's empty for tokens the lexer
+// makes up, like indents and unindents.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyntaxToken {
+    pub token: Token,
+    pub leading_trivia: Vec<Trivia>,
+    pub text: String,
+}
+
+// Text between tokens that doesn't affect what the code means.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum TriviaKind {
+    // Spaces and tabs, including indentation.
+    Whitespace,
+    // Newlines that don't end a statement, such as ones inside brackets.
+    LineBreak,
+}
+
+// Lexes the source code into a syntax tree. Like 'lex_with_recovery', this
+// always produces a tree, along with every error found.
+#[must_use]
+pub fn parse_cst(source: &str) -> (SyntaxNode, Vec<LexerError>) {
+    let (tokens, errors) = lex_with_recovery(source);
+    (Builder::new(source, tokens).build(), errors)
+}
+
+impl SyntaxNode {
+    // The tokens in the tree, in the form the parser expects.
+    #[must_use]
+    pub fn tokens(&self) -> Vec<Token> {
+        let mut out = Vec::new();
+        self.collect_tokens(&mut out);
+        out
+    }
+
+    #[must_use]
+    pub fn to_ast(&self) -> (Program, Vec<ParserError>) {
+        parse(&self.tokens())
+    }
+
+    fn collect_tokens(&self, out: &mut Vec<Token>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(out),
+                SyntaxElement::Token(token) if token.token.kind != TokenKind::EndOfFile => {
+                    out.push(token.token.clone());
+                },
+                SyntaxElement::Token(_) => {},
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => fmt::Display::fmt(node, f)?,
+                SyntaxElement::Token(token) => fmt::Display::fmt(token, f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for trivia in &self.leading_trivia {
+            f.write_str(&trivia.text)?;
+        }
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lex;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_round_trip() {
+        let sources = [
+            "",
+            "   \n\n",
+            "x = 1",
+            concat!(
+                "#comment without a space\n",
+                "fn foo(a: int,\n",
+                "       b: int) -> int:   \n",
+                "\t  return  a+b # add\n",
+                "\n",
+                "   \n",
+                "        var x = [\r\n",
+                "            1,   2,\r\n",
+                "        ]\r\n",
+                "print(\"😀 \\n\")  $$ 0q1\n",
+                "   ",
+            ),
+        ];
+        for source in sources {
+            let (cst, _) = parse_cst(source);
+            assert_eq!(cst.to_string(), source);
+        }
+    }
+
+    // Shows the structure of the tree, with the trivia before each token.
+    fn outline(node: &SyntaxNode) -> String {
+        let children: Vec<String> = node.children.iter()
+            .map(|child| match child {
+                SyntaxElement::Node(node) => outline(node),
+                SyntaxElement::Token(token) => {
+                    let trivia: String = token.leading_trivia.iter()
+                        .map(|trivia| format!("{:?}{:?} ", trivia.kind, trivia.text))
+                        .collect();
+                    format!("{}{:?}", trivia, token.text)
+                },
+            })
+            .collect();
+        format!("{:?}({})", node.kind, children.join(" "))
+    }
+
+    #[test]
+    fn test_layout() {
+        let (cst, _) = parse_cst("if x:\n    a  \n\nb(1,\n  2)\n");
+        assert_eq!(outline(&cst), concat!(
+            r#"File("#,
+            r#"Line("if" Whitespace" " "x" ":" "\n") "#,
+            r#"Block("" Line(Whitespace"    " "a" Whitespace"  " "\n") "") "#,
+            r#"Line("\n") "#,
+            r#"Line("b" "(" "1" "," LineBreak"\n" Whitespace"  " "2" ")" "\n") "#,
+            r#""")"#,
+        ));
+    }
+
+    #[test]
+    fn test_to_ast() {
+        let source = concat!(
+            "# A counter\n",
+            "class Counter:\n",
+            "    count: Int\n",
+            "\n",
+            "    fn increment(amount: Int) -> Int:\n",
+            "        this.count = this.count  +  amount\n",
+            "        return this.count\n",
+        );
+        let (cst, lexer_errors) = parse_cst(source);
+        let (program, parser_errors) = cst.to_ast();
+        assert!(lexer_errors.is_empty());
+        assert!(parser_errors.is_empty());
+        let tokens = lex(source).unwrap_or_default();
+        assert_eq!(program, parse(&tokens).0);
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+// Every diagnostic has a code, so tools can tell problems apart without
+// scraping the message. Codes are stable: never reuse or renumber one, and
+// add a new code instead of changing what an existing one means.
+//
+// Lexer errors use E01xx and parser errors use E02xx. E03xx is reserved
+// for the type checker.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ErrorCode {
+    // Lexer
+    InternalLexerError,
+    UnknownCharacter,
+    TabIndentation,
+    IndentNotMultipleOfFour,
+    InvalidNumber,
+    InvalidTupleIndex,
+    UnterminatedString,
+    CommentMissingSpace,
+    InvalidEscape,
+
+    // Parser
+    InternalParserError,
+    UnexpectedToken,
+    MisplacedImport,
+    DuplicateConstructor,
+    TryWithoutCatch,
+    LoopExitOutsideLoop,
+    MissingReturnType,
+}
+
+impl ErrorCode {
+    #[must_use]
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorCode::InternalLexerError => "E0100",
+            ErrorCode::UnknownCharacter => "E0101",
+            ErrorCode::TabIndentation => "E0102",
+            ErrorCode::IndentNotMultipleOfFour => "E0103",
+            ErrorCode::InvalidNumber => "E0104",
+            ErrorCode::InvalidTupleIndex => "E0105",
+            ErrorCode::UnterminatedString => "E0106",
+            ErrorCode::CommentMissingSpace => "E0107",
+            ErrorCode::InvalidEscape => "E0108",
+            ErrorCode::InternalParserError => "E0200",
+            ErrorCode::UnexpectedToken => "E0201",
+            ErrorCode::MisplacedImport => "E0202",
+            ErrorCode::DuplicateConstructor => "E0203",
+            ErrorCode::TryWithoutCatch => "E0204",
+            ErrorCode::LoopExitOutsideLoop => "E0205",
+            ErrorCode::MissingReturnType => "E0206",
+        }
+    }
+
+    // A short, human-readable name for the code.
+    #[must_use]
+    pub fn slug(self) -> &'static str {
+        match self {
+            ErrorCode::InternalLexerError => "internal-lexer-error",
+            ErrorCode::UnknownCharacter => "unknown-character",
+            ErrorCode::TabIndentation => "tab-indentation",
+            ErrorCode::IndentNotMultipleOfFour => "indent-not-multiple-of-four",
+            ErrorCode::InvalidNumber => "invalid-number",
+            ErrorCode::InvalidTupleIndex => "invalid-tuple-index",
+            ErrorCode::UnterminatedString => "unterminated-string",
+            ErrorCode::CommentMissingSpace => "comment-missing-space",
+            ErrorCode::InvalidEscape => "invalid-escape",
+            ErrorCode::InternalParserError => "internal-parser-error",
+            ErrorCode::UnexpectedToken => "unexpected-token",
+            ErrorCode::MisplacedImport => "misplaced-import",
+            ErrorCode::DuplicateConstructor => "duplicate-constructor",
+            ErrorCode::TryWithoutCatch => "try-without-catch",
+            ErrorCode::LoopExitOutsideLoop => "loop-exit-outside-loop",
+            ErrorCode::MissingReturnType => "missing-return-type",
+        }
+    }
+
+    // How serious problems with this code are. Only errors stop the
+    // program from being compiled.
+    #[must_use]
+    pub fn severity(self) -> Severity {
+        match self {
+            ErrorCode::CommentMissingSpace => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
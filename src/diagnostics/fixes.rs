@@ -0,0 +1,127 @@
+use super::Diagnostic;
+use crate::tokens::Position;
+
+// A suggested change to the source code that would fix a problem.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Fix {
+    pub message: String,
+    pub applicability: Applicability,
+    pub edits: Vec<Edit>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Edit {
+    pub span: (Position, Position),
+    pub replacement: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Applicability {
+    // The fix is definitely what the user meant, so it's safe to apply
+    // without asking.
+    MachineApplicable,
+    // The fix is a guess: it's valid code, but may not be what the user
+    // meant.
+    MaybeIncorrect,
+}
+
+impl Applicability {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Applicability::MachineApplicable => "machine-applicable",
+            Applicability::MaybeIncorrect => "maybe-incorrect",
+        }
+    }
+}
+
+impl Fix {
+    #[must_use]
+    pub fn replace(span: (Position, Position), replacement: impl Into<String>, message: impl Into<String>) -> Fix {
+        Fix{
+            message: message.into(),
+            applicability: Applicability::MachineApplicable,
+            edits: vec![Edit{span, replacement: replacement.into()}],
+        }
+    }
+
+    #[must_use]
+    pub fn insert(position: Position, text: impl Into<String>, message: impl Into<String>) -> Fix {
+        Fix::replace((position, position), text, message)
+    }
+
+    #[must_use]
+    pub fn with_applicability(mut self, applicability: Applicability) -> Fix {
+        self.applicability = applicability;
+        self
+    }
+}
+
+// Applies every machine-applicable fix attached to the diagnostics, and
+// returns the fixed source code. If the edits of two fixes overlap, only
+// the first is applied.
+#[must_use]
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut edits: Vec<&Edit> = Vec::new();
+    for fix in diagnostics.iter().filter_map(|d| d.fix.as_ref()) {
+        if fix.applicability != Applicability::MachineApplicable {
+            continue;
+        }
+        let clashes = fix.edits.iter().any(|edit| {
+            edits.iter().any(|prev| overlaps(prev.span, edit.span))
+        });
+        if !clashes {
+            edits.extend(&fix.edits);
+        }
+    }
+    edits.sort_by_key(|edit| edit.span.0.offset);
+
+    let mut out = String::with_capacity(source.len());
+    let mut prev_end = 0;
+    for edit in edits {
+        let (start, end) = edit.span;
+        out.push_str(source.get(prev_end..start.offset).unwrap_or_default());
+        out.push_str(&edit.replacement);
+        prev_end = end.offset;
+    }
+    out.push_str(source.get(prev_end..).unwrap_or_default());
+    out
+}
+
+// Insertions at the same position overlap, since we wouldn't know what
+// order to insert them in.
+fn overlaps(left: (Position, Position), right: (Position, Position)) -> bool {
+    let (left_start, left_end) = (left.0.offset, left.1.offset);
+    let (right_start, right_end) = (right.0.offset, right.1.offset);
+    left_start == right_start || (left_start < right_end && right_start < left_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ErrorCode;
+    use pretty_assertions::assert_eq;
+
+    fn pos(offset: usize) -> Position {
+        Position::new(0, offset, offset)
+    }
+
+    fn diagnostic(fix: Fix) -> Diagnostic {
+        Diagnostic::new(ErrorCode::InternalLexerError, "").with_fix(fix)
+    }
+
+    #[test]
+    fn test_apply_fixes() {
+        // Offsets are in bytes, and 'é' is two bytes long
+        let source = "é\tabc def";
+        let diagnostics = vec![
+            diagnostic(Fix::insert(pos(10), ":", "")),
+            diagnostic(Fix::replace((pos(2), pos(3)), "    ", "")),
+            diagnostic(Fix::replace((pos(3), pos(6)), "xyz", "").with_applicability(Applicability::MaybeIncorrect)),
+            // Overlaps the first replacement, so isn't applied
+            diagnostic(Fix::replace((pos(2), pos(4)), "", "")),
+            Diagnostic::new(ErrorCode::InternalLexerError, ""),
+        ];
+        assert_eq!(apply_fixes(source, &diagnostics), "é    abc def:");
+    }
+}
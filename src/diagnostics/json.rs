@@ -0,0 +1,152 @@
+use super::{Diagnostic, Fix, Label};
+use crate::tokens::Position;
+
+// Serializes diagnostics as a JSON array, for tools that need to consume
+// errors without scraping the rendered text. Each diagnostic looks like:
+//
+//     {"code": "E0103", "name": "indent-not-multiple-of-four",
+//      "severity": "error", "message": "...",
+//      "primary": {"start": {...}, "end": {...}, "message": "..."},
+//      "secondary": [...], "notes": [...],
+//      "fix": {"message": "...", "applicability": "machine-applicable",
+//              "edits": [{"start": {...}, "end": {...}, "replacement": "..."}]}}
+//
+// 'primary' is null if we don't know where the problem is, and 'fix' is
+// null if there's no suggested fix. Positions have a 1-based line and
+// columns (w.r.t. characters and UTF-16 code units), and a 0-based byte
+// offset into the source; the end of a span is exclusive.
+#[must_use]
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    let items: Vec<String> = diagnostics.iter()
+        .map(diagnostic_to_json)
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> String {
+    let primary = match &diagnostic.primary {
+        Some(label) => label_to_json(label),
+        None => "null".to_owned(),
+    };
+    let secondary: Vec<String> = diagnostic.secondary.iter()
+        .map(label_to_json)
+        .collect();
+    let notes: Vec<String> = diagnostic.notes.iter()
+        .map(|note| quote(note))
+        .collect();
+    let fix = match &diagnostic.fix {
+        Some(fix) => fix_to_json(fix),
+        None => "null".to_owned(),
+    };
+    format!(
+        "{{\"code\":{},\"name\":{},\"severity\":{},\"message\":{},\"primary\":{},\"secondary\":[{}],\"notes\":[{}],\"fix\":{}}}",
+        quote(diagnostic.code.code()),
+        quote(diagnostic.code.slug()),
+        quote(diagnostic.severity.name()),
+        quote(&diagnostic.message),
+        primary,
+        secondary.join(","),
+        notes.join(","),
+        fix,
+    )
+}
+
+fn label_to_json(label: &Label) -> String {
+    let (start, end) = label.span;
+    format!(
+        "{{\"start\":{},\"end\":{},\"message\":{}}}",
+        position_to_json(start),
+        position_to_json(end),
+        quote(&label.message),
+    )
+}
+
+fn fix_to_json(fix: &Fix) -> String {
+    let edits: Vec<String> = fix.edits.iter()
+        .map(|edit| format!(
+            "{{\"start\":{},\"end\":{},\"replacement\":{}}}",
+            position_to_json(edit.span.0),
+            position_to_json(edit.span.1),
+            quote(&edit.replacement),
+        ))
+        .collect();
+    format!(
+        "{{\"message\":{},\"applicability\":{},\"edits\":[{}]}}",
+        quote(&fix.message),
+        quote(fix.applicability.name()),
+        edits.join(","),
+    )
+}
+
+fn position_to_json(position: Position) -> String {
+    format!(
+        "{{\"line\":{},\"column\":{},\"utf16_column\":{},\"offset\":{}}}",
+        position.lineno + 1,
+        position.column + 1,
+        position.utf16_column + 1,
+        position.offset,
+    )
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => out.extend(format!("\\u{:04x}", u32::from(c)).chars()),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{ErrorCode, Severity};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_json() {
+        // Just after a '😀' at the start of a line, which is four bytes
+        // and two UTF-16 code units long.
+        let after_emoji = Position{lineno: 0, column: 1, utf16_column: 2, offset: 4};
+        let diagnostics = vec![
+            Diagnostic::new(ErrorCode::IndentNotMultipleOfFour, "Bad \"indent\"")
+                .with_primary((Position::new(1, 0, 7), Position::new(1, 2, 9)), "here")
+                .with_secondary((after_emoji, after_emoji.add_horizontal(1)), "")
+                .with_note("a\tnote")
+                .with_fix(Fix::replace((Position::new(1, 0, 7), Position::new(1, 2, 9)), "    ", "use four spaces")),
+            Diagnostic::new(ErrorCode::InternalParserError, "Oops")
+                .with_severity(Severity::Warning),
+        ];
+        let expected = concat!(
+            "[",
+            "{\"code\":\"E0103\",\"name\":\"indent-not-multiple-of-four\",\"severity\":\"error\",",
+            "\"message\":\"Bad \\\"indent\\\"\",",
+            "\"primary\":{\"start\":{\"line\":2,\"column\":1,\"utf16_column\":1,\"offset\":7},",
+            "\"end\":{\"line\":2,\"column\":3,\"utf16_column\":3,\"offset\":9},\"message\":\"here\"},",
+            "\"secondary\":[{\"start\":{\"line\":1,\"column\":2,\"utf16_column\":3,\"offset\":4},",
+            "\"end\":{\"line\":1,\"column\":3,\"utf16_column\":4,\"offset\":5},\"message\":\"\"}],",
+            "\"notes\":[\"a\\tnote\"],",
+            "\"fix\":{\"message\":\"use four spaces\",\"applicability\":\"machine-applicable\",",
+            "\"edits\":[{\"start\":{\"line\":2,\"column\":1,\"utf16_column\":1,\"offset\":7},",
+            "\"end\":{\"line\":2,\"column\":3,\"utf16_column\":3,\"offset\":9},\"replacement\":\"    \"}]}},",
+            "{\"code\":\"E0200\",\"name\":\"internal-parser-error\",\"severity\":\"warning\",",
+            "\"message\":\"Oops\",\"primary\":null,\"secondary\":[],\"notes\":[],\"fix\":null}",
+            "]",
+        );
+        assert_eq!(to_json(&diagnostics), expected);
+    }
+
+    #[test]
+    fn test_quote_control_chars() {
+        assert_eq!(quote("a\u{1}b\\"), "\"a\\u0001b\\\\\"");
+    }
+}
@@ -0,0 +1,188 @@
+mod codes;
+mod fixes;
+mod json;
+mod render;
+
+pub use self::codes::{ErrorCode, Severity};
+pub use self::fixes::{apply_fixes, Applicability, Edit, Fix};
+pub use self::json::to_json;
+pub use self::render::Renderer;
+
+use crate::cst::parse_cst;
+use crate::lexer::LexerError;
+use crate::parser::ParserError;
+use crate::tokens::Position;
+
+// A problem with the source code, along with the parts of the source code
+// it refers to. Use a 'Renderer' to display it, or 'to_json' to hand it
+// to other tools.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub severity: Severity,
+    pub message: String,
+    // This is None if we don't know where the problem is.
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub fix: Option<Fix>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Label {
+    pub span: (Position, Position),
+    pub message: String,
+}
+
+impl Diagnostic {
+    #[must_use]
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic{
+            code,
+            severity: code.severity(),
+            message: message.into(),
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            fix: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_severity(mut self, severity: Severity) -> Diagnostic {
+        self.severity = severity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_primary(mut self, span: (Position, Position), message: impl Into<String>) -> Diagnostic {
+        self.primary = Some(Label{span, message: message.into()});
+        self
+    }
+
+    #[must_use]
+    pub fn with_secondary(mut self, span: (Position, Position), message: impl Into<String>) -> Diagnostic {
+        self.secondary.push(Label{span, message: message.into()});
+        self
+    }
+
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_fix(mut self, fix: Fix) -> Diagnostic {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+impl From<&LexerError> for Diagnostic {
+    fn from(err: &LexerError) -> Diagnostic {
+        let mut out = Diagnostic::new(err.code, &err.message)
+            .with_primary((err.position, err.end), "");
+        if let Some(fix) = &err.fix {
+            out = out.with_fix((**fix).clone());
+        }
+        out
+    }
+}
+
+// Any errors the parser error was caused by are attached as secondary
+// labels, or as notes if we don't know where they are.
+impl From<&ParserError> for Diagnostic {
+    fn from(err: &ParserError) -> Diagnostic {
+        let mut out = Diagnostic::new(err.code, &err.message);
+        if let Some(span) = err.span {
+            out = out.with_primary(span, "");
+        }
+        let mut curr = &err.source;
+        while let Some(source) = curr {
+            out = match source.span {
+                Some(span) => out.with_secondary(span, &source.message),
+                None => out.with_note(&source.message),
+            };
+            curr = &source.source;
+        }
+        if let Some(fix) = &err.fix {
+            out = out.with_fix((**fix).clone());
+        }
+        out
+    }
+}
+
+// Lexes and parses the source code, and returns every problem found.
+#[must_use]
+pub fn check(source: &str) -> Vec<Diagnostic> {
+    let (cst, lexer_errors) = parse_cst(source);
+    let (_, parser_errors) = cst.to_ast();
+    collect_diagnostics(&lexer_errors, &parser_errors)
+}
+
+// Combines the lexer and parser errors in the order they appear in the
+// source code. Errors we don't know the position of go last.
+#[must_use]
+pub fn collect_diagnostics(lexer_errors: &[LexerError], parser_errors: &[ParserError]) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = lexer_errors.iter().map(Diagnostic::from)
+        .chain(parser_errors.iter().map(Diagnostic::from))
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| {
+        diagnostic.primary.as_ref().map_or(usize::MAX, |label| label.span.0.offset)
+    });
+    diagnostics
+}
+
+// Applies every machine-applicable fix for the problems in the source
+// code, and returns the fixed source code.
+#[must_use]
+pub fn fix(source: &str) -> String {
+    apply_fixes(source, &check(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_fix() {
+        let source = concat!(
+            "#comment\n",
+            "while x\n",
+            "\tif y:\n",
+            "         print(x)\n",
+            "      print(y)\n",
+        );
+        // The last line could be meant to be in either block, so it's left
+        // alone.
+        let expected = concat!(
+            "# comment\n",
+            "while x:\n",
+            "    if y:\n",
+            "        print(x)\n",
+            "      print(y)\n",
+        );
+        assert_eq!(fix(source), expected);
+    }
+
+    #[test]
+    fn test_check_orders_by_position() {
+        let source = concat!(
+            "while x\n",
+            "    print(x)\n",
+            "var y = 0b\n",
+        );
+        let codes: Vec<ErrorCode> = check(source).iter().map(|d| d.code).collect();
+        assert_eq!(codes, vec![ErrorCode::UnexpectedToken, ErrorCode::InvalidNumber]);
+    }
+
+    #[test]
+    fn test_lexer_error_spans_whole_char() {
+        // '€' is three bytes long
+        let diagnostics = check("a = €\n");
+        let span = diagnostics.first().and_then(|d| d.primary.as_ref()).map(|label| label.span);
+        assert_eq!(span.map(|(start, end)| (start.offset, end.offset)), Some((4, 7)));
+    }
+}
@@ -0,0 +1,264 @@
+use super::{Diagnostic, Label, Severity};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+
+const TAB_WIDTH: usize = 4;
+
+// Displays diagnostics in a similar style to rustc: the message, followed
+// by the lines of source code being referred to with the labelled parts
+// underlined, followed by any notes.
+pub struct Renderer<'a> {
+    lines: Vec<&'a str>,
+    path: Option<&'a str>,
+    color: bool,
+}
+
+// An underline below part of a single source line. Columns are w.r.t.
+// characters, and the end is exclusive.
+struct Underline<'a> {
+    lineno: usize,
+    start_column: usize,
+    end_column: usize,
+    is_primary: bool,
+    // Only the last line of a multi-line label is given the message.
+    message: Option<&'a str>,
+}
+
+impl<'a> Renderer<'a> {
+    #[must_use]
+    pub fn new(source: &'a str) -> Renderer<'a> {
+        Renderer{
+            lines: source.lines().collect(),
+            path: None,
+            color: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_path(mut self, path: &'a str) -> Renderer<'a> {
+        self.path = Some(path);
+        self
+    }
+
+    // Whether to use ANSI escape codes to color the output.
+    #[must_use]
+    pub fn with_color(mut self, color: bool) -> Renderer<'a> {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut underlines = Vec::new();
+        if let Some(label) = &diagnostic.primary {
+            self.add_underlines(&mut underlines, label, true);
+        }
+        for label in &diagnostic.secondary {
+            self.add_underlines(&mut underlines, label, false);
+        }
+        underlines.sort_by_key(|u| (u.lineno, !u.is_primary, u.start_column));
+
+        let gutter_width = underlines.iter()
+            .map(|u| (u.lineno + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let empty_gutter = " ".repeat(gutter_width);
+
+        let mut out = Vec::new();
+        let color = severity_color(diagnostic.severity);
+        let header = format!("{}[{}]", diagnostic.severity.name(), diagnostic.code);
+        out.push(format!("{}: {}", self.paint(color, &header), self.paint(BOLD, &diagnostic.message)));
+
+        if let Some(label) = &diagnostic.primary {
+            let (start, _) = label.span;
+            let location = match self.path {
+                Some(path) => format!("{}:{}:{}", path, start.lineno + 1, start.column + 1),
+                None => format!("{}:{}", start.lineno + 1, start.column + 1),
+            };
+            out.push(format!("{empty_gutter}{} {location}", self.paint(BOLD_BLUE, "-->")));
+        }
+
+        if !underlines.is_empty() {
+            out.push(format!("{empty_gutter} {}", self.paint(BOLD_BLUE, "|")));
+        }
+        let mut prev_lineno = None;
+        for underline in &underlines {
+            if prev_lineno != Some(underline.lineno) {
+                if prev_lineno.is_some_and(|prev| underline.lineno > prev + 1) {
+                    out.push(self.paint(BOLD_BLUE, "..."));
+                }
+                let gutter = format!("{:>width$} |", underline.lineno + 1, width = gutter_width);
+                let line = self.lines.get(underline.lineno).copied().unwrap_or_default();
+                out.push(format!("{} {}", self.paint(BOLD_BLUE, &gutter), expand_tabs(line).trim_end()));
+                prev_lineno = Some(underline.lineno);
+            }
+            out.push(format!("{empty_gutter} {}", self.render_underline(underline)));
+        }
+
+        let has_footer = !diagnostic.notes.is_empty() || diagnostic.fix.is_some();
+        if has_footer && !underlines.is_empty() {
+            out.push(format!("{empty_gutter} {}", self.paint(BOLD_BLUE, "|")));
+        }
+        for note in &diagnostic.notes {
+            out.push(format!("{empty_gutter} {} {}", self.paint(BOLD_BLUE, "="), self.paint(BOLD, &format!("note: {note}"))));
+        }
+        if let Some(fix) = &diagnostic.fix {
+            out.push(format!("{empty_gutter} {} {}", self.paint(BOLD_BLUE, "="), self.paint(BOLD, &format!("help: {}", fix.message))));
+        }
+        out.push(String::new());
+        out.join("\n")
+    }
+
+    fn add_underlines<'b>(&self, underlines: &mut Vec<Underline<'b>>, label: &'b Label, is_primary: bool) {
+        let (start, end) = label.span;
+        let last_lineno = if end.lineno > start.lineno && end.column == 0 {
+            end.lineno - 1
+        } else {
+            end.lineno.max(start.lineno)
+        };
+        for lineno in start.lineno..=last_lineno {
+            let line = self.lines.get(lineno).copied().unwrap_or_default();
+            let start_column = if lineno == start.lineno {
+                start.column
+            } else {
+                line.chars().take_while(|c| c.is_whitespace()).count()
+            };
+            let end_column = if lineno == end.lineno {
+                end.column
+            } else {
+                line.chars().count()
+            };
+            underlines.push(Underline{
+                lineno,
+                start_column,
+                end_column: end_column.max(start_column + 1),
+                is_primary,
+                message: (lineno == last_lineno && !label.message.is_empty()).then_some(label.message.as_str()),
+            });
+        }
+    }
+
+    fn render_underline(&self, underline: &Underline) -> String {
+        let line = self.lines.get(underline.lineno).copied().unwrap_or_default();
+        let padding = display_width(line, 0, underline.start_column);
+        let width = display_width(line, underline.start_column, underline.end_column);
+        let (marker, color) = if underline.is_primary { ("^", BOLD_RED) } else { ("-", BOLD_BLUE) };
+
+        let mut marks = marker.repeat(width);
+        if let Some(message) = underline.message {
+            marks = format!("{marks} {message}");
+        }
+        format!("{} {}{}", self.paint(BOLD_BLUE, "|"), " ".repeat(padding), self.paint(color, &marks))
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => BOLD_RED,
+        Severity::Warning => BOLD_YELLOW,
+        Severity::Note => BOLD_BLUE,
+    }
+}
+
+fn expand_tabs(line: &str) -> String {
+    line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+// The number of columns the given chars of the line take up once
+// displayed. Columns past the end of the line take up one column each.
+fn display_width(line: &str, start_column: usize, end_column: usize) -> usize {
+    let mut chars = line.chars().skip(start_column);
+    (start_column..end_column)
+        .map(|_| if chars.next() == Some('\t') { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{ErrorCode, Fix};
+    use crate::tokens::Position;
+    use pretty_assertions::assert_eq;
+
+    fn pos(lineno: usize, column: usize) -> Position {
+        Position::new(lineno, column, 0)
+    }
+
+    #[test]
+    fn test_render_labels_and_notes() {
+        let source = "var a = 1\nwhile a > 3 4:\n    print(a)\n";
+        let diagnostic = Diagnostic::new(ErrorCode::UnexpectedToken, "Expected Colon, got IntLiteral")
+            .with_primary((pos(1, 12), pos(1, 13)), "expected ':' here")
+            .with_secondary((pos(1, 0), pos(1, 5)), "while loop starts here")
+            .with_secondary((pos(0, 4), pos(0, 5)), "")
+            .with_note("loop conditions must be followed by ':'")
+            .with_fix(Fix::insert(pos(1, 13), ":", "add a `:` here"));
+        let expected = concat!(
+            "error[E0201]: Expected Colon, got IntLiteral\n",
+            " --> test.impo:2:13\n",
+            "  |\n",
+            "1 | var a = 1\n",
+            "  |     -\n",
+            "2 | while a > 3 4:\n",
+            "  |             ^ expected ':' here\n",
+            "  | ----- while loop starts here\n",
+            "  |\n",
+            "  = note: loop conditions must be followed by ':'\n",
+            "  = help: add a `:` here\n",
+        );
+        assert_eq!(Renderer::new(source).with_path("test.impo").render(&diagnostic), expected);
+    }
+
+    #[test]
+    fn test_render_multiline_span() {
+        let source = "fn foo():\n\tvar x = (1 +\n\t\t2\n\nprint(x)\n";
+        let diagnostic = Diagnostic::new(ErrorCode::UnexpectedToken, "Bad expression")
+            .with_primary((pos(1, 9), pos(2, 3)), "in this expression")
+            .with_secondary((pos(4, 0), pos(4, 5)), "");
+        let expected = concat!(
+            "error[E0201]: Bad expression\n",
+            " --> 2:10\n",
+            "  |\n",
+            "2 |     var x = (1 +\n",
+            "  |             ^^^^\n",
+            "3 |         2\n",
+            "  |         ^ in this expression\n",
+            "...\n",
+            "5 | print(x)\n",
+            "  | -----\n",
+        );
+        assert_eq!(Renderer::new(source).render(&diagnostic), expected);
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let diagnostic = Diagnostic::new(ErrorCode::InternalParserError, "Something went wrong")
+            .with_severity(Severity::Warning)
+            .with_note("a note");
+        let expected = concat!(
+            "warning[E0200]: Something went wrong\n",
+            " = note: a note\n",
+        );
+        assert_eq!(Renderer::new("").render(&diagnostic), expected);
+    }
+
+    #[test]
+    fn test_render_color() {
+        let diagnostic = Diagnostic::new(ErrorCode::UnknownCharacter, "Oops").with_primary((pos(0, 0), pos(0, 1)), "");
+        let rendered = Renderer::new("x\n").with_color(true).render(&diagnostic);
+        assert!(rendered.starts_with("\x1b[1;31merror[E0101]\x1b[0m: \x1b[1mOops\x1b[0m\n"));
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
@@ -1,35 +1,35 @@
 use crate::tokens::Position;
 
-pub struct CharStream {
-    stream: Vec<char>,
-    index: usize,
+// Reads the input one char at a time. The stream's position has the byte
+// offset of the next char, so the text of a token can be sliced straight
+// out of the input instead of being copied char by char.
+pub struct CharStream<'a> {
+    input: &'a str,
     position: Position,
 }
 
-impl CharStream {
-    pub fn new(input: &str) -> CharStream {
-        CharStream{ 
-            stream: input.chars().collect(),
-            index: 0,
+impl<'a> CharStream<'a> {
+    pub fn new(input: &'a str) -> CharStream<'a> {
+        CharStream{
+            input: input,
             position: Position::start(),
         }
     }
 
     pub fn read_char(&mut self) -> Option<char> {
-        let out = self.stream.get(self.index).copied();
+        let out = self.peek_char();
         if let Some(c) = out {
-            self.index += 1;
             self.position.advance(c);
         }
         out
     }
 
-    pub fn peek_char(&mut self) -> Option<char> {
-        self.stream.get(self.index).copied()
+    pub fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
     }
 
-    pub fn peek_char_at_offset(&mut self, offset: usize) -> Option<char> {
-        self.stream.get(self.index + offset).copied()
+    pub fn peek_char_at_offset(&self, offset: usize) -> Option<char> {
+        self.rest().chars().nth(offset)
     }
 
     pub fn read_if_char(&mut self, possible: char) -> bool {
@@ -51,17 +51,15 @@ impl CharStream {
         }
     }
 
-    pub fn read_while(&mut self, filter: fn(char) -> bool) -> String {
-        let mut out = String::new();
-        while let Some(c) = self.read_if(filter) {
-            out.push(c);
-        }
-        out
+    pub fn read_while(&mut self, filter: fn(char) -> bool) -> &'a str {
+        let start = self.position.offset;
+        self.skip_while(filter);
+        self.slice_from(start)
     }
 
     pub fn skip_while(&mut self, filter: fn(char) -> bool) -> usize {
         let mut num_skipped = 0;
-        while self.read_if(filter).is_some() { 
+        while self.read_if(filter).is_some() {
             num_skipped += 1;
         }
         num_skipped
@@ -70,4 +68,40 @@ impl CharStream {
     pub fn position(&self) -> Position {
         self.position
     }
-}
\ No newline at end of file
+
+    // Moves back to a position we've already passed.
+    pub fn reset_to(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    // The input from the given byte offset up to the current position.
+    pub fn slice_from(&self, start: usize) -> &'a str {
+        self.input.get(start..self.position.offset).unwrap_or_default()
+    }
+
+    // The input from the current position onwards.
+    pub fn rest(&self) -> &'a str {
+        self.input.get(self.position.offset..).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        // 'é' is two bytes and one UTF-16 unit, and '😀' is four bytes and
+        // two UTF-16 units.
+        let mut stream = CharStream::new("é😀\nab");
+        assert_eq!(stream.read_while(|c| c != '\n'), "é😀");
+        assert_eq!(stream.position(), Position{lineno: 0, column: 2, utf16_column: 3, offset: 6});
+        assert_eq!(stream.read_char(), Some('\n'));
+        assert_eq!(stream.read_char(), Some('a'));
+        assert_eq!(stream.position(), Position{lineno: 1, column: 1, utf16_column: 1, offset: 8});
+        assert_eq!(stream.peek_char_at_offset(1), None);
+
+        stream.reset_to(Position::start());
+        assert_eq!(stream.peek_char(), Some('é'));
+    }
+}
@@ -6,10 +6,31 @@ use super::lex_numbers::*;
 use super::lex_simple::*;
 use super::lex_strings::*;
 
+use crate::diagnostics::{Applicability, ErrorCode, Fix, Severity};
 use crate::tokens::*;
 
+// The number of spaces in each indent level. Tabs count as one level.
+const INDENT_WIDTH: usize = 4;
+
 pub fn lex(text: impl AsRef<str>) -> Result<Vec<Token>, LexerError> {
-    let mut lexer = Lexer::new(text.as_ref());
+    lex_all(&mut Lexer::new(text.as_ref()))
+}
+
+// Like 'lex', but doesn't stop at the first error. Instead, the bad text is
+// replaced by an error token and lexing carries on, so the rest of the file
+// can still be used. Returns the tokens along with every error found.
+pub fn lex_with_recovery(text: impl AsRef<str>) -> (Vec<Token>, Vec<LexerError>) {
+    let mut lexer = Lexer::new_recovering(text.as_ref());
+    match lex_all(&mut lexer) {
+        Ok(tokens) => (tokens, lexer.errors),
+        Err(err) => {
+            lexer.errors.push(err);
+            (Vec::new(), lexer.errors)
+        },
+    }
+}
+
+fn lex_all(lexer: &mut Lexer) -> Result<Vec<Token>, LexerError> {
     let mut tokens = Vec::new();
     loop {
         let tok = lexer.next_token()?;
@@ -22,7 +43,8 @@ pub fn lex(text: impl AsRef<str>) -> Result<Vec<Token>, LexerError> {
         if last.kind != TokenKind::Newline {
             let extra_newline = Token{
                 kind: TokenKind::Newline,
-                position: last.end_position(),
+                position: last.end,
+                end: last.end,
             };
             tokens.push(extra_newline);
         }
@@ -32,8 +54,13 @@ pub fn lex(text: impl AsRef<str>) -> Result<Vec<Token>, LexerError> {
 
 #[derive(thiserror::Error, Debug)]
 pub struct LexerError {
+    pub code: ErrorCode,
+    // The error covers the source from 'position' up to 'end'.
     pub position: Position,
+    pub end: Position,
     pub message: String,
+    // Set if there's an obvious way to fix the error.
+    pub fix: Option<Box<Fix>>,
 }
 
 impl fmt::Display for LexerError {
@@ -42,25 +69,42 @@ impl fmt::Display for LexerError {
     }
 }
 
-pub struct Lexer {
-    stream: CharStream,
+pub struct Lexer<'a> {
+    stream: CharStream<'a>,
     indent_level: usize,
     brace_level: usize,
+    at_line_start: bool,
+    after_dot: bool,
     queued: VecDeque<Token>,
+    // If set, errors are recorded instead of returned. See 'lex_with_recovery'.
+    recovering: bool,
+    errors: Vec<LexerError>,
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Lexer {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
         Lexer{ 
             stream: CharStream::new(input),
             indent_level: 0,
             brace_level: 0,
+            at_line_start: true,
+            after_dot: false,
             queued: VecDeque::new(),
+            recovering: false,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn new_recovering(input: &'a str) -> Lexer<'a> {
+        Lexer{
+            recovering: true,
+            ..Lexer::new(input)
         }
     }
 
     pub fn next_token(&mut self) -> Result<Token, LexerError> {
         if let Some(tok) = self.queued.pop_front() {
+            self.at_line_start = true;
             return Ok(tok);
         }
 
@@ -86,44 +130,149 @@ impl Lexer {
             return Ok(Token{
                 kind: kind,
                 position: start,
+                end: start,
             })
         };
 
-        let kind = if let Some(kind) = self.match_parentheses(c) {
+        let kind = match self.match_kind(c, start) {
+            Ok(Some(kind)) => kind,
+            Ok(None) => {
+                if let Some(tokens) = self.match_newline(c, start)? {
+                    for token in tokens {
+                        self.queued.push_back(token);
+                    }
+                    self.at_line_start = true;
+                    let fatal_err = LexerError{
+                        code: ErrorCode::InternalLexerError,
+                        position: start,
+                        end: start,
+                        message: "Unexpected fatal error with newline/indent parser".to_owned(),
+                        fix: None,
+                    };
+                    return match self.queued.pop_front() {
+                        Some(t) => Ok(t),
+                        None => Err(fatal_err),
+                    };
+                }
+                self.recover(c, start, LexerError{
+                    code: ErrorCode::UnknownCharacter,
+                    position: start,
+                    end: start.after(c),
+                    message: format!("Could not parse character '{c}'"),
+                    fix: None,
+                })?
+            },
+            Err(err) => self.recover(c, start, err)?,
+        };
+
+        self.at_line_start = false;
+        self.after_dot = kind == TokenKind::Dot;
+        Ok(Token{
+            position: start,
+            end: self.stream.position(),
+            kind: kind,
+        })
+    }
+
+    fn match_kind(&mut self, c: char, start: Position) -> Result<Option<TokenKind>, LexerError> {
+        let kind = if let Some(kind) = self.match_hint(c) {
+            kind
+        } else if let Some(kind) = self.match_parentheses(c) {
             kind
         } else if let Some(kind) = match_simple_operator(&mut self.stream, c) {
             kind
-        } else if let Some(kind) = match_comment(&mut self.stream, c) {
+        } else if let Some(kind) = self.match_comment(c)? {
             kind
         } else if let Some(kind) = match_identifier_or_keyword(&mut self.stream, c) {
             kind
+        } else if let Some(kind) = self.match_tuple_index(c, start)? {
+            kind
         } else if let Some(kind) = match_number(&mut self.stream, c)? {
             kind
-        } else if let Some(kind) = match_string(&mut self.stream, c)? {
+        } else if let Some(kind) = match_string(&mut self.stream, c, start)? {
             kind
-        } else if let Some(tokens) = self.match_newline(c, start)? {
-            for token in tokens {
-                self.queued.push_back(token);
-            }
-            let fatal_err = LexerError{
-                position: start,
-                message: "Unexpected fatal error with newline/indent parser".to_owned(),
-            };
-            return Ok(match self.queued.pop_front() {
-                Some(t) => t,
-                None => { return Err(fatal_err) }
-            });
         } else {
-            return Err(LexerError{
-                position: start,
-                message: format!("Could not parse character '{}'", c),
-            });
+            return Ok(None);
         };
+        Ok(Some(kind))
+    }
 
-        Ok(Token{
-            position: start,
-            kind: kind,
-        })
+    // Records the error if we're recovering from errors, or returns it if
+    // not. Errors found while lexing a token replace it with an error token.
+    // Warnings never stop us, but are only recorded if we're recovering.
+    fn report(&mut self, err: LexerError) -> Result<(), LexerError> {
+        if self.recovering {
+            self.errors.push(err);
+            Ok(())
+        } else if err.code.severity() == Severity::Error {
+            Err(err)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn recover(&mut self, c: char, start: Position, err: LexerError) -> Result<TokenKind, LexerError> {
+        self.report(err)?;
+        self.resync(c, start);
+        Ok(TokenKind::Error)
+    }
+
+    // Skips past the rest of a bad token that started with 'c', so we can
+    // carry on lexing from somewhere sensible.
+    fn resync(&mut self, c: char, start: Position) {
+        self.stream.reset_to(start);
+        let _ = self.stream.read_char();
+        if c == '"' {
+            // Skip to the closing quote. Unterminated strings would otherwise
+            // swallow the rest of the file, so we stop at the end of the line.
+            while let Some(nc) = self.stream.read_if(is_not_newline) {
+                if nc == '"' {
+                    break;
+                }
+                if nc == '\\' {
+                    let _ = self.stream.read_if(is_not_newline);
+                }
+            }
+        } else if c.is_ascii_digit() {
+            self.stream.skip_while(|nc| nc.is_alphanumeric() || nc == '_' || nc == '.');
+        } else {
+            self.stream.skip_while(is_unknown_char);
+        }
+    }
+
+    // A '!' is the start of a hint only if it's the first thing on the line
+    // and is followed by a target, like `!Java: ...`; everywhere else, it's
+    // the logical negation operator.
+    fn match_hint(&mut self, c: char) -> Option<TokenKind> {
+        let is_hint = c == '!' && self.at_line_start && starts_with_hint_target(self.stream.rest());
+        is_hint.then_some(TokenKind::Hint)
+    }
+
+    // Comments should have a space after the '#'. We still accept ones
+    // without, but warn about them.
+    fn match_comment(&mut self, c: char) -> Result<Option<TokenKind>, LexerError> {
+        let after_hash = self.stream.position();
+        let after_hash_char = self.stream.peek_char().filter(|&nc| c == '#' && nc != ' ' && is_not_newline(nc));
+        if let Some(nc) = after_hash_char {
+            self.report(LexerError{
+                code: ErrorCode::CommentMissingSpace,
+                position: after_hash,
+                end: after_hash.after(nc),
+                message: "Comments should have a space after the '#'".to_owned(),
+                fix: Some(Box::new(Fix::insert(after_hash, " ", "add a space after the `#`"))),
+            })?;
+        }
+        Ok(match_comment(&mut self.stream, c))
+    }
+
+    // Digits right after a '.' are a tuple index, not a float: `t.0.1` is
+    // the lookup chain `t`, `0`, `1` rather than `t` followed by `0.1`.
+    fn match_tuple_index(&mut self, c: char, start: Position) -> Result<Option<TokenKind>, LexerError> {
+        if self.after_dot {
+            match_tuple_index(&mut self.stream, c, start)
+        } else {
+            Ok(None)
+        }
     }
 
     fn match_parentheses(&mut self, c: char) -> Option<TokenKind> {
@@ -148,6 +297,38 @@ impl Lexer {
         Some(kind)
     }
 
+    // Works out the indent level of a line from the width of its indent.
+    // Tabs and indents that aren't a multiple of four are errors; if we're
+    // recovering, we round to the nearest level and suggest reindenting.
+    fn check_indent(&mut self, indent: (Position, Position), width: usize, first_tab: Option<Position>) -> Result<usize, LexerError> {
+        let is_halfway = width % INDENT_WIDTH == INDENT_WIDTH / 2;
+        let level = if is_halfway {
+            // Blocks only ever go one level deeper, so we only round up if
+            // this could be a new block.
+            (width / INDENT_WIDTH + 1).min(self.indent_level + 1)
+        } else {
+            (width + INDENT_WIDTH / 2) / INDENT_WIDTH
+        };
+        let (code, (position, end), message) = if let Some(tab) = first_tab {
+            (ErrorCode::TabIndentation, (tab, tab.after('\t')), "Tabs cannot be used as an indentation char".to_owned())
+        } else if !width.is_multiple_of(INDENT_WIDTH) {
+            (ErrorCode::IndentNotMultipleOfFour, indent, format!("Indent contains {width} spaces: must be a multiple of four"))
+        } else {
+            return Ok(level);
+        };
+        // Halfway between two levels, either could have been meant
+        let applicability = if is_halfway {
+            Applicability::MaybeIncorrect
+        } else {
+            Applicability::MachineApplicable
+        };
+        let spaces = level * INDENT_WIDTH;
+        let fix = Fix::replace(indent, " ".repeat(spaces), format!("indent with {spaces} spaces"))
+            .with_applicability(applicability);
+        self.report(LexerError{code, position, end, message, fix: Some(Box::new(fix))})?;
+        Ok(level)
+    }
+
     fn match_newline(&mut self, c: char, original_start: Position) -> Result<Option<Vec<Token>>, LexerError> {
         if self.brace_level != 0 || is_not_newline(c) {
             return Ok(None)
@@ -161,26 +342,31 @@ impl Lexer {
 
         loop {
             // Push newline for current line
+            if line_end_char == '\r' {
+                let _ = self.stream.read_if_char('\n');
+            }
             acc.push(Token { 
                 kind: TokenKind::Newline, 
                 position: curr_line_start,
+                end: self.stream.position(),
             });
 
             // Compute indentation level for next line
-            if line_end_char == '\r' {
-                let _ = self.stream.read_if_char('\n');
-            }
-            let mut num_spaces = 0;
-            while let Some(sc) = self.stream.read_if(|sc| sc == ' ' || sc == '\t') { 
-                if sc == ' ' {
-                    num_spaces += 1;
-                } else if sc == 't' {
-                    return Err(LexerError{
-                        position: self.stream.position(),
-                        message: "Tabs cannot be used as an indentation char".to_owned(),
-                    })
+            let indent_start = self.stream.position();
+            let mut width = 0;
+            let mut first_tab = None;
+            loop {
+                let position = self.stream.position();
+                match self.stream.read_if(|sc| sc == ' ' || sc == '\t') {
+                    Some('\t') => {
+                        first_tab.get_or_insert(position);
+                        width += INDENT_WIDTH;
+                    },
+                    Some(_) => width += 1,
+                    None => break,
                 }
             }
+            let indent = (indent_start, self.stream.position());
 
             // Determine if we need to move on to the next line
             line_end_char = if let Some(nc) = self.stream.peek_char() {
@@ -192,13 +378,7 @@ impl Lexer {
                     nc
                 } else {
                     // Current line is non-empty. End and handle indents/unindents
-                    if num_spaces % 4 != 0 {
-                        return Err(LexerError{
-                            position: curr_line_start,
-                            message: format!("Indent contains {} spaces: must be a multiple of four", num_spaces),
-                        })
-                    }
-                    new_indent_level = num_spaces / 4;
+                    new_indent_level = self.check_indent(indent, width, first_tab)?;
                     break;
                 }
             } else {
@@ -213,24 +393,27 @@ impl Lexer {
                 // Indents always go at the end. This ensures empty lines belong to the current block
                 let delta = new_indent_level - self.indent_level;
                 for _ in 0..delta {
-                    acc.push(Token { kind: TokenKind::Indent, position: curr_line_start });
+                    acc.push(Token { kind: TokenKind::Indent, position: curr_line_start, end: curr_line_start });
                 }
             } else {
                 // Invariant: the list always contains at least one newline
                 let (first, rest) = acc.split_first().ok_or(LexerError{
+                    code: ErrorCode::InternalLexerError,
                     message: "Unexpected fatal error: newline lexer did not find any newlines".to_owned(),
                     position: original_start,
+                    end: original_start,
+                    fix: None,
                 })?;
 
                 // Dedents go after the first newline. This ensures empty lines belong to the new block
                 let delta = self.indent_level - new_indent_level;
                 let mut new_acc = vec![first.clone()];
                 for _ in 0..delta {
-                    new_acc.push(Token{kind: TokenKind::Unindent, position: original_start});
+                    new_acc.push(Token{kind: TokenKind::Unindent, position: original_start, end: original_start});
                 }
                 new_acc.extend_from_slice(rest);
                 acc = new_acc;
-            };
+            }
         }
         self.indent_level = new_indent_level;
 
@@ -246,9 +429,15 @@ pub fn is_not_newline(c: char) -> bool {
     !is_newline(c)
 }
 
+// Chars that can't start any token.
+fn is_unknown_char(c: char) -> bool {
+    !(c.is_whitespace() || c.is_alphanumeric() || c == '_' || "\"#()[]{}+-*/%<>=!~|&^.:,".contains(c))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::lexer::test_utils::*;
+    use crate::diagnostics::{Applicability, Edit};
 
     #[test]
     fn test_indentation() -> Result<(), LexerError> {
@@ -394,6 +583,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hints() -> Result<(), LexerError> {
+        lexer_test_ignore_positions(
+            concat!(
+                "!Java: Omit()\n",
+                "if !a != b:\n",
+                "    !Go: Inline\n",
+                "    !flag\n",
+            ),
+            vec![
+                TokenKind::Hint,
+                TokenKind::Atom("Java".into()),
+                TokenKind::Colon,
+                TokenKind::Atom("Omit".into()),
+                TokenKind::LParen,
+                TokenKind::RParen,
+                TokenKind::Newline,
+                TokenKind::If,
+                TokenKind::Bang,
+                TokenKind::Atom("a".into()),
+                TokenKind::NotEquals,
+                TokenKind::Atom("b".into()),
+                TokenKind::Colon,
+                TokenKind::Newline,
+                TokenKind::Indent,
+                TokenKind::Hint,
+                TokenKind::Atom("Go".into()),
+                TokenKind::Colon,
+                TokenKind::Atom("Inline".into()),
+                TokenKind::Newline,
+                TokenKind::Bang,
+                TokenKind::Atom("flag".into()),
+                TokenKind::Newline,
+                TokenKind::Unindent,
+            ].iter(),
+        )
+    }
+
     #[test]
     fn test_indentation_ending_with_many_newline() -> Result<(), LexerError> {
         lexer_test(
@@ -408,56 +635,168 @@ mod tests {
                 Token{
                     kind: TokenKind::Atom("foo1".into()),
                     position: Position::new(0, 0, 0),
+                    end: Position::new(0, 4, 4),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(0, 4, 4),
+                    end: Position::new(1, 0, 5),
                 },
                 Token{
                     kind: TokenKind::Indent,
                     position: Position::new(0, 4, 4),
+                    end: Position::new(0, 4, 4),
                 },
                 Token{
                     kind: TokenKind::Atom("foo2".into()),
                     position: Position::new(1, 4, 9),
+                    end: Position::new(1, 8, 13),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(1, 8, 13),
+                    end: Position::new(2, 0, 14),
                 },
                 Token{
                     kind: TokenKind::Indent,
                     position: Position::new(1, 8, 13),
+                    end: Position::new(1, 8, 13),
                 },
                 Token{
                     kind: TokenKind::Atom("foo3".into()),
                     position: Position::new(2, 8, 22),
+                    end: Position::new(2, 12, 26),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(2, 12, 26),
+                    end: Position::new(3, 0, 27),
                 },
                 Token{
                     kind: TokenKind::Unindent,
                     position: Position::new(2, 12, 26),
+                    end: Position::new(2, 12, 26),
                 },
                 Token{
                     kind: TokenKind::Unindent,
                     position: Position::new(2, 12, 26),
+                    end: Position::new(2, 12, 26),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(3, 0, 27),
+                    end: Position::new(4, 0, 28),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(4, 0, 28),
+                    end: Position::new(5, 0, 29),
                 },
                 Token{
                     kind: TokenKind::EndOfFile,
                     position: Position::new(5, 0, 29),
+                    end: Position::new(5, 0, 29),
                 },
             ].into_iter()
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_recovery() {
+        let (tokens, errors) = super::lex_with_recovery(concat!(
+            "a = 0q12 $$ b\n",
+            "      c\n",
+            "f = \"\\q\" + g\n",
+            "d = \"oops\n",
+            "e\n",
+        ));
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Atom("a".into()),
+            TokenKind::Assign,
+            TokenKind::Error,
+            TokenKind::Error,
+            TokenKind::Atom("b".into()),
+            TokenKind::Newline,
+            TokenKind::Indent,
+            TokenKind::Atom("c".into()),
+            TokenKind::Newline,
+            TokenKind::Unindent,
+            TokenKind::Atom("f".into()),
+            TokenKind::Assign,
+            TokenKind::Error,
+            TokenKind::Plus,
+            TokenKind::Atom("g".into()),
+            TokenKind::Newline,
+            TokenKind::Atom("d".into()),
+            TokenKind::Assign,
+            TokenKind::Error,
+            TokenKind::Newline,
+            TokenKind::Atom("e".into()),
+            TokenKind::Newline,
+        ]);
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "[1,6] Initial 0 must be followed by x, o, or b; followed by 'q' instead",
+            "[1,10] Could not parse character '$'",
+            "[2,1] Indent contains 6 spaces: must be a multiple of four",
+            "[3,6] Unknown escape sequence '\\q'",
+            "[4,5] Unexpected end of string",
+        ]);
+    }
+
+    #[test]
+    fn test_spans() {
+        let (tokens, _) = super::lex_with_recovery("x = 1.5e-3 instanceof $$ #c\n");
+        let spans: Vec<(usize, usize)> = tokens.iter()
+            .map(|token| (token.position.offset, token.end.offset))
+            .collect();
+        assert_eq!(spans, vec![(0, 1), (2, 3), (4, 10), (11, 21), (22, 24), (25, 27), (27, 28)]);
+    }
+
+    #[test]
+    fn test_fixes() {
+        let (tokens, errors) = super::lex_with_recovery(concat!(
+            "a:\n",
+            "\tb  # comment\n",
+            "     #c\n",
+            "d\n",
+        ));
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Atom("a".into()),
+            TokenKind::Colon,
+            TokenKind::Newline,
+            TokenKind::Indent,
+            TokenKind::Atom("b".into()),
+            TokenKind::Comment("comment".into()),
+            TokenKind::Newline,
+            TokenKind::Comment("c".into()),
+            TokenKind::Newline,
+            TokenKind::Unindent,
+            TokenKind::Atom("d".into()),
+            TokenKind::Newline,
+        ]);
+        let fixes: Vec<(String, Applicability, Vec<Edit>)> = errors.into_iter()
+            .filter_map(|err| err.fix)
+            .map(|fix| (fix.message, fix.applicability, fix.edits))
+            .collect();
+        assert_eq!(fixes, vec![
+            (
+                "indent with 4 spaces".to_owned(),
+                Applicability::MachineApplicable,
+                vec![Edit{span: (Position::new(1, 0, 3), Position::new(1, 1, 4)), replacement: "    ".to_owned()}],
+            ),
+            (
+                "indent with 4 spaces".to_owned(),
+                Applicability::MachineApplicable,
+                vec![Edit{span: (Position::new(2, 0, 17), Position::new(2, 5, 22)), replacement: "    ".to_owned()}],
+            ),
+            (
+                "add a space after the `#`".to_owned(),
+                Applicability::MachineApplicable,
+                vec![Edit{span: (Position::new(2, 6, 23), Position::new(2, 6, 23)), replacement: " ".to_owned()}],
+            ),
+        ]);
+    }
+}
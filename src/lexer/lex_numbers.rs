@@ -1,5 +1,6 @@
 use super::char_stream::CharStream;
 use super::core::LexerError;
+use crate::diagnostics::ErrorCode;
 use crate::tokens::*;
 use crate::values::{IntLiteral, FloatLiteral};
 
@@ -12,27 +13,83 @@ enum NumState {
     SciNotationDigits,
 }
 
+// 'pos' is the position of 'nc', the char after the 0.
 fn make_bad_initial_zero_err(nc: char, pos: Position) -> Result<Option<TokenKind>, LexerError> {
     Err(LexerError{
+        code: ErrorCode::InvalidNumber,
         message: format!("Initial 0 must be followed by x, o, or b; followed by '{}' instead", nc),
         position: pos,
+        end: pos.after(nc),
+        fix: None,
     })
 }
 
 fn make_nonstandard_base_into_floating_point_err(pos: Position) -> Result<Option<TokenKind>, LexerError> {
     Err(LexerError{
+        code: ErrorCode::InvalidNumber,
         message: "Cannot prefix floating point numbers with 0x, 0o, or 0b".to_owned(),
         position: pos,
+        end: pos.after('.'),
+        fix: None,
     })
 }
 
-fn make_bad_sci_notation_transition_err(seq: &str, pos: Position) -> Result<Option<TokenKind>, LexerError> {
+// Points at whatever comes after the prefix instead of a digit.
+fn make_missing_digits_err(base: u32, stream: &CharStream) -> Result<Option<TokenKind>, LexerError> {
+    let pos = stream.position();
+    let prefix = match base {
+        16 => "0x",
+        8 => "0o",
+        _ => "0b",
+    };
     Err(LexerError{
+        code: ErrorCode::InvalidNumber,
+        message: format!("Expected at least one digit after '{prefix}'"),
+        position: pos,
+        end: stream.peek_char().map_or(pos, |nc| pos.after(nc)),
+        fix: None,
+    })
+}
+
+fn make_bad_sci_notation_transition_err(seq: &str, pos: Position, end: Position) -> Result<Option<TokenKind>, LexerError> {
+    Err(LexerError{
+        code: ErrorCode::InvalidNumber,
         message: format!("Scientific notation contained invalid char '{}'", seq),
         position: pos,
+        end: end,
+        fix: None,
     })
 }
 
+// 'start' is the position of the first digit, 'c'.
+pub fn match_tuple_index(stream: &mut CharStream, c: char, start: Position) -> Result<Option<TokenKind>, LexerError> {
+    if !is_digit(c) {
+        return Ok(None)
+    }
+    let mut digits = c.to_string();
+    while let Some(nc) = stream.read_if(is_digit) {
+        digits.push(nc);
+    }
+    if digits.len() > 1 && c == '0' {
+        return Err(LexerError{
+            code: ErrorCode::InvalidTupleIndex,
+            message: format!("Tuple index '{digits}' cannot have leading zeros"),
+            position: start,
+            end: stream.position(),
+            fix: None,
+        });
+    }
+    IntLiteral::new(10, digits.into())
+        .map(|lit| Some(TokenKind::IntLiteral(lit)))
+        .map_err(|err| LexerError{
+            code: ErrorCode::InvalidTupleIndex,
+            message: format!("Invalid tuple index: {err}"),
+            position: start,
+            end: stream.position(),
+            fix: None,
+        })
+}
+
 #[allow(clippy::too_many_lines)]
 pub fn match_number(stream: &mut CharStream, c: char) -> Result<Option<TokenKind>, LexerError> {
     if !is_digit(c) {
@@ -66,13 +123,14 @@ pub fn match_number(stream: &mut CharStream, c: char) -> Result<Option<TokenKind
                         NumState::FractionalDigits
                     }
                     Some(nc) if nc.is_ascii_alphabetic() => {
+                        let pos = stream.position();
                         let _ = stream.read_char();
                         base = match nc {
                             'x' => 16,
                             'o' => 8,
                             'b' => 2,
                             _ => {
-                                return make_bad_initial_zero_err(nc, stream.position());
+                                return make_bad_initial_zero_err(nc, pos);
                             }
                         };
                         NumState::IntegralDigits
@@ -137,10 +195,12 @@ pub fn match_number(stream: &mut CharStream, c: char) -> Result<Option<TokenKind
                         NumState::SciNotationDigits
                     },
                     Some(nc) => {
-                        return make_bad_sci_notation_transition_err(&nc.to_string(), stream.position());
+                        let pos = stream.position();
+                        return make_bad_sci_notation_transition_err(&nc.to_string(), pos, pos.after(nc));
                     }
                     None => {
-                        return make_bad_sci_notation_transition_err("EOF", stream.position());
+                        let pos = stream.position();
+                        return make_bad_sci_notation_transition_err("EOF", pos, pos);
                     }
                 }
 
@@ -160,12 +220,18 @@ pub fn match_number(stream: &mut CharStream, c: char) -> Result<Option<TokenKind
         }
     }
 
+    if integral_digits.is_empty() {
+        return make_missing_digits_err(base, stream);
+    }
     let kind = if fractional_digits.is_empty() {
         IntLiteral::new(base, integral_digits.into())
             .map(TokenKind::IntLiteral)
             .map_err(|err| LexerError{
+                code: ErrorCode::InternalLexerError,
                 message: format!("Unexpected fatal error parsing number: {}", err),
                 position: stream.position(),
+                end: stream.position(),
+                fix: None,
             })?
     } else {
         TokenKind::FloatLiteral(FloatLiteral{
@@ -303,4 +369,32 @@ mod tests {
             ].into_iter(),
         )
     }
+
+    #[test]
+    fn test_tuple_index() -> Result<(), LexerError> {
+        lexer_test_ignore_positions(
+            "t.0.12 ",
+            vec![
+                TokenKind::Atom("t".into()),
+                TokenKind::Dot,
+                TokenKind::IntLiteral(IntLiteral{
+                    base: 10,
+                    digits: "0".into(),
+                    raw_value: 0,
+                }),
+                TokenKind::Dot,
+                TokenKind::IntLiteral(IntLiteral{
+                    base: 10,
+                    digits: "12".into(),
+                    raw_value: 12,
+                }),
+            ].into_iter(),
+        )
+    }
+
+    #[test]
+    fn test_bad_tuple_index() {
+        let message = crate::lexer::lex("t.01").err().map(|err| err.to_string());
+        assert_eq!(message.as_deref(), Some("[1,3] Tuple index '01' cannot have leading zeros"));
+    }
 }
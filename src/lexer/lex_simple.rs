@@ -4,53 +4,37 @@ use crate::tokens::*;
 
 pub fn match_simple_operator(stream: &mut CharStream, c: char) -> Option<TokenKind> {
     Some(match c {
-        '+' => TokenKind::Plus,
+        '+' => with_assign(stream, TokenKind::Plus, TokenKind::PlusAssign),
         '-' => {
             if stream.read_if_char('>') {
                 TokenKind::Arrow
             } else {
-                TokenKind::Minus
+                with_assign(stream, TokenKind::Minus, TokenKind::MinusAssign)
             }
         },
-        '*' => TokenKind::Multiply,
-        '/' => TokenKind::Divide,
-        '%' => TokenKind::Percent,
+        '*' => with_assign(stream, TokenKind::Multiply, TokenKind::MultiplyAssign),
+        '/' => with_assign(stream, TokenKind::Divide, TokenKind::DivideAssign),
+        '%' => with_assign(stream, TokenKind::Percent, TokenKind::PercentAssign),
         '>' => {
-            if stream.read_if_char('=') {
-                TokenKind::GreaterThanEquals
-            } else if stream.read_if_char('>') {
-                TokenKind::ShiftRight
+            if stream.read_if_char('>') {
+                with_assign(stream, TokenKind::ShiftRight, TokenKind::ShiftRightAssign)
             } else {
-                TokenKind::GreaterThan
+                with_assign(stream, TokenKind::GreaterThan, TokenKind::GreaterThanEquals)
             }
         },
         '<' => {
-            if stream.read_if_char('=') {
-                TokenKind::LessThanEquals
-            } else if stream.read_if_char('<') {
-                TokenKind::ShiftLeft
-            } else {
-                TokenKind::LessThan
-            }
-        },
-        '=' => {
-            if stream.read_if_char('=') {
-                TokenKind::Equals
+            if stream.read_if_char('<') {
+                with_assign(stream, TokenKind::ShiftLeft, TokenKind::ShiftLeftAssign)
             } else {
-                TokenKind::Assign
-            }
-        },
-        '!' => {
-            if stream.read_if_char('=') {
-                TokenKind::NotEquals
-            } else {
-                TokenKind::Bang
+                with_assign(stream, TokenKind::LessThan, TokenKind::LessThanEquals)
             }
         },
+        '=' => with_assign(stream, TokenKind::Assign, TokenKind::Equals),
+        '!' => with_assign(stream, TokenKind::Bang, TokenKind::NotEquals),
         '~' => TokenKind::Tilde,
-        '|' => TokenKind::Pipe,
-        '&' => TokenKind::Ampersand,
-        '^' => TokenKind::Caret,
+        '|' => with_assign(stream, TokenKind::Pipe, TokenKind::PipeAssign),
+        '&' => with_assign(stream, TokenKind::Ampersand, TokenKind::AmpersandAssign),
+        '^' => with_assign(stream, TokenKind::Caret, TokenKind::CaretAssign),
         '.' => TokenKind::Dot,
         ':' => TokenKind::Colon,
         ',' => TokenKind::Comma,
@@ -60,12 +44,22 @@ pub fn match_simple_operator(stream: &mut CharStream, c: char) -> Option<TokenKi
     })
 }
 
+// For operators that mean something else when followed by a '=', like
+// `+` and `+=`, or `<` and `<=`.
+fn with_assign(stream: &mut CharStream, plain: TokenKind, assign: TokenKind) -> TokenKind {
+    if stream.read_if_char('=') {
+        assign
+    } else {
+        plain
+    }
+}
+
 pub fn match_comment(stream: &mut CharStream, c: char) -> Option<TokenKind> {
     if c != '#' {
         return None
     }
     let _ = stream.read_if_char(' ');
-    Some(TokenKind::Comment(stream.read_while(is_not_newline)))
+    Some(TokenKind::Comment(stream.read_while(is_not_newline).to_owned()))
 }
 
 pub fn match_identifier_or_keyword(stream: &mut CharStream, c: char) -> Option<TokenKind> {
@@ -73,11 +67,11 @@ pub fn match_identifier_or_keyword(stream: &mut CharStream, c: char) -> Option<T
         return None
     }
 
-    let mut identifier = String::new();
-    identifier.push(c);
-    identifier.push_str(&stream.read_while(is_identifier));
+    let start = stream.position().offset - c.len_utf8();
+    stream.skip_while(is_identifier);
+    let identifier = stream.slice_from(start);
 
-    Some(match identifier.as_str() {
+    Some(match identifier {
         // Operator keywords
         "instanceof" => TokenKind::InstanceOf,
         "or" => TokenKind::Or,
@@ -87,27 +81,48 @@ pub fn match_identifier_or_keyword(stream: &mut CharStream, c: char) -> Option<T
         "if" => TokenKind::If,
         "elif" => TokenKind::Elif,
         "else" => TokenKind::Else,
+        "match" => TokenKind::Match,
+        "case" => TokenKind::Case,
         "for" => TokenKind::For,
         "from" => TokenKind::From,
         "to" => TokenKind::To,
+        "downto" => TokenKind::DownTo,
+        "by" => TokenKind::By,
         "foreach" => TokenKind::Foreach,
         "in" => TokenKind::In,
         "while" => TokenKind::While,
+        "break" => TokenKind::Break,
+        "continue" => TokenKind::Continue,
         "return" => TokenKind::Return,
         "panic" => TokenKind::Panic,
+        "try" => TokenKind::Try,
+        "catch" => TokenKind::Catch,
+        "throw" => TokenKind::Throw,
+        "throws" => TokenKind::Throws,
         "fn" => TokenKind::Fn,
         "constructor" => TokenKind::Constructor,
         "interface" => TokenKind::Interface,
         "class" => TokenKind::Class,
         "sentinal" => TokenKind::Sentinal,
         "const" => TokenKind::Const,
+        "var" => TokenKind::Var,
         "implements" => TokenKind::Implements,
+        "import" => TokenKind::Import,
+        "as" => TokenKind::As,
         "true" => TokenKind::BoolLiteral(true),
         "false" => TokenKind::BoolLiteral(false),
         _ => TokenKind::Atom(identifier.into()),
     })
 }
 
+// Whether the text starts with a name followed by a ':', like the `Java:`
+// in the hint `!Java: Omit()`.
+pub fn starts_with_hint_target(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(is_identifier_start)
+        && chars.as_str().trim_start_matches(is_identifier).trim_start_matches(' ').starts_with(':')
+}
+
 fn is_identifier_start(c: char) -> bool {
     c == '_' || c.is_alphabetic()
 }
@@ -146,6 +161,7 @@ mod tests {
                 Token{
                     kind: kind.clone(),
                     position: Position::new(0, index * 2, index * 2),
+                    end: Position::new(0, index * 2 + 1, index * 2 + 1),
                 }
             }),
         )
@@ -169,6 +185,34 @@ mod tests {
                 Token{
                     kind: kind.clone(),
                     position: Position::new(0, index * 3, index * 3),
+                    end: Position::new(0, index * 3 + 2, index * 3 + 2),
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_lex_compound_assignment_operators() -> Result<(), LexerError> {
+        let input = "+= -= *= /= %= |= ^= &= <<= >>=";
+        let expected = vec![
+            (TokenKind::PlusAssign, 0, 2),
+            (TokenKind::MinusAssign, 3, 5),
+            (TokenKind::MultiplyAssign, 6, 8),
+            (TokenKind::DivideAssign, 9, 11),
+            (TokenKind::PercentAssign, 12, 14),
+            (TokenKind::PipeAssign, 15, 17),
+            (TokenKind::CaretAssign, 18, 20),
+            (TokenKind::AmpersandAssign, 21, 23),
+            (TokenKind::ShiftLeftAssign, 24, 27),
+            (TokenKind::ShiftRightAssign, 28, 31),
+        ];
+        lexer_test(
+            input,
+            expected.into_iter().map(|(kind, start, end)| {
+                Token{
+                    kind: kind,
+                    position: Position::new(0, start, start),
+                    end: Position::new(0, end, end),
                 }
             }),
         )
@@ -186,26 +230,32 @@ mod tests {
                 Token{
                     kind: TokenKind::Comment("comment 1".to_owned()),
                     position: Position::new(0, 0, 0),
+                    end: Position::new(0, 11, 11),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(0, 11, 11),
+                    end: Position::new(1, 0, 12),
                 },
                 Token{
                     kind: TokenKind::Comment("comment # with # extra # hash".to_owned()),
                     position: Position::new(1, 0, 12),
+                    end: Position::new(1, 31, 43),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(1, 31, 43),
+                    end: Position::new(2, 0, 45),
                 },
                 Token{
                     kind: TokenKind::Comment("final comment".to_owned()),
                     position: Position::new(2, 0, 45),
+                    end: Position::new(2, 15, 60),
                 },
                 Token{
                     kind: TokenKind::Newline,
                     position: Position::new(2, 15, 60),
+                    end: Position::new(2, 16, 61),
                 },
             ].iter(),
         )
@@ -219,26 +269,32 @@ mod tests {
                 Token{
                     kind: TokenKind::For,
                     position: Position::new(0, 0, 0),
+                    end: Position::new(0, 3, 3),
                 },
                 Token{
                     kind: TokenKind::And,
                     position: Position::new(0, 4, 4),
+                    end: Position::new(0, 7, 7),
                 },
                 Token{
                     kind: TokenKind::Implements,
                     position: Position::new(0, 8, 8),
+                    end: Position::new(0, 18, 18),
                 },
                 Token{
                     kind: TokenKind::Atom("foo".into()),
                     position: Position::new(0, 19, 19),
+                    end: Position::new(0, 22, 22),
                 },
                 Token{
                     kind: TokenKind::Atom("b12".into()),
                     position: Position::new(0, 23, 23),
+                    end: Position::new(0, 26, 26),
                 },
                 Token{
                     kind: TokenKind::Atom("andvar".into()),
                     position: Position::new(0, 27, 27),
+                    end: Position::new(0, 33, 33),
                 },
             ].iter(),
         )
@@ -1,98 +1,186 @@
 use super::char_stream::CharStream;
 use super::core::LexerError;
+use crate::diagnostics::ErrorCode;
 use crate::tokens::*;
+use crate::values::StringLiteral;
 
 const QUOTE_CHAR: char = '"';
 const ESCAPE_START: char = '\\';
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug)]
-enum StringState {
-    RegularChar,
-    Escape,
-}
+// The most hex digits a '\u{...}' escape can have.
+const MAX_UNICODE_DIGITS: usize = 6;
 
-pub fn match_string(stream: &mut CharStream, c: char) -> Result<Option<TokenKind>, LexerError> {
+// Strings that never end are reported at their opening quote, which is at
+// 'start'.
+pub fn match_string(stream: &mut CharStream, c: char, start: Position) -> Result<Option<TokenKind>, LexerError> {
     if c != QUOTE_CHAR {
         return Ok(None);
     }
 
-    let mut output = String::new();
-    let mut state = StringState::RegularChar;
+    let raw_start = stream.position().offset;
+    let mut value = String::new();
     loop {
         let pos = stream.position();
         let curr = stream
             .read_char()
-            .ok_or_else(|| make_unexpected_end_of_string_err(pos))?;
-        state = match state {
-            StringState::RegularChar => {
-                match curr {
-                    ESCAPE_START => StringState::Escape,
-                    QUOTE_CHAR => {
-                        break
-                    },
-                    _ => {
-                        output.push(curr);
-                        StringState::RegularChar
-                    }
-                }
-            },
-            StringState::Escape => {
-                output.push(escape_symbol_to_char(curr));
-                StringState::RegularChar
+            .ok_or_else(|| make_unexpected_end_of_string_err(start, stream.position()))?;
+        match curr {
+            ESCAPE_START if stream.peek_char().is_none() => {
+                return Err(make_unexpected_end_of_string_err(start, stream.position()));
             },
+            ESCAPE_START => value.push(read_escape(stream, pos)?),
+            QUOTE_CHAR => break,
+            _ => value.push(curr),
         }
     }
 
-    Ok(Some(TokenKind::StringLiteral(output)))
+    let raw = stream.slice_from(raw_start);
+    Ok(Some(TokenKind::StringLiteral(Box::new(StringLiteral{
+        value: value,
+        raw: raw.strip_suffix(QUOTE_CHAR).unwrap_or(raw).to_owned(),
+    }))))
 }
 
-fn make_unexpected_end_of_string_err(pos: Position) -> LexerError {
+fn make_unexpected_end_of_string_err(start: Position, end: Position) -> LexerError {
     LexerError{
+        code: ErrorCode::UnterminatedString,
         message: "Unexpected end of string".to_owned(),
-        position: pos,
+        position: start,
+        end: end,
+        fix: None,
+    }
+}
+
+// Bad escapes are reported from the backslash they start with up to the
+// last char read.
+fn make_bad_escape_err(stream: &CharStream, start: Position, message: String) -> LexerError {
+    LexerError{
+        code: ErrorCode::InvalidEscape,
+        message: message,
+        position: start,
+        end: stream.position(),
+        fix: None,
     }
 }
 
-fn escape_symbol_to_char(symbol: char) -> char {
+// Reads the rest of an escape sequence, given the position of the
+// backslash before it.
+fn read_escape(stream: &mut CharStream, start: Position) -> Result<char, LexerError> {
+    let pos = stream.position();
+    let symbol = stream
+        .read_char()
+        .ok_or_else(|| make_unexpected_end_of_string_err(pos, pos))?;
     match symbol {
-        'n' => '\n',
-        'r' => '\r',
-        't' => '\t',
-        _ => symbol,
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        '0' => Ok('\0'),
+        '\\' | '"' | '\'' => Ok(symbol),
+        'x' => read_hex_escape(stream, start),
+        'u' => read_unicode_escape(stream, start),
+        _ => Err(make_bad_escape_err(stream, start, format!("Unknown escape sequence '\\{symbol}'"))),
     }
 }
 
+// '\xNN' can only be used for ASCII chars, since other targets disagree on
+// whether it means a byte or a code point.
+fn read_hex_escape(stream: &mut CharStream, start: Position) -> Result<char, LexerError> {
+    let digits = read_hex_digits(stream, 2);
+    if digits.len() != 2 {
+        return Err(make_bad_escape_err(stream, start, "Expected two hex digits after '\\x'".to_owned()));
+    }
+    match u8::from_str_radix(digits, 16) {
+        Ok(byte) if byte.is_ascii() => Ok(char::from(byte)),
+        _ => Err(make_bad_escape_err(stream, start, format!(
+            "'\\x{digits}' is not an ASCII char: use '\\u{{{digits}}}' instead",
+        ))),
+    }
+}
+
+fn read_unicode_escape(stream: &mut CharStream, start: Position) -> Result<char, LexerError> {
+    if !stream.read_if_char('{') {
+        return Err(make_bad_escape_err(stream, start, "Expected '{' after '\\u'".to_owned()));
+    }
+    let digits = read_hex_digits(stream, MAX_UNICODE_DIGITS);
+    if digits.is_empty() || !stream.read_if_char('}') {
+        return Err(make_bad_escape_err(stream, start, format!(
+            "'\\u{{...}}' must contain between 1 and {MAX_UNICODE_DIGITS} hex digits",
+        )));
+    }
+    u32::from_str_radix(digits, 16).ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| make_bad_escape_err(stream, start, format!("'\\u{{{digits}}}' is not a valid char")))
+}
+
+fn read_hex_digits<'a>(stream: &mut CharStream<'a>, max_digits: usize) -> &'a str {
+    let start = stream.position().offset;
+    for _ in 0..max_digits {
+        if stream.read_if(|c| c.is_ascii_hexdigit()).is_none() {
+            break;
+        }
+    }
+    stream.slice_from(start)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::lexer::test_utils::*;
+    use crate::values::StringLiteral;
 
     #[test]
     fn test_string() -> Result<(), LexerError> {
         lexer_test(
             concat!(
                 r#""hello world" "#,
-                r#""\n\r\t\\\"\'\q" "#,
+                r#""\n\r\t\\\"\'\0\x41\u{1F600}" "#,
                 r#""😀" "#,
                 r#""" "#,
             ),
             vec![
                 Token{
                     position: Position::new(0, 0, 0),
-                    kind: TokenKind::StringLiteral("hello world".to_owned()),
+                    end: Position::new(0, 13, 13),
+                    kind: TokenKind::StringLiteral(Box::new("hello world".into())),
                 },
                 Token{
                     position: Position::new(0, 14, 14),
-                    kind: TokenKind::StringLiteral("\n\r\t\\\"\'q".to_owned()),
+                    end: Position::new(0, 43, 43),
+                    kind: TokenKind::StringLiteral(Box::new(StringLiteral{
+                        value: "\n\r\t\\\"\'\0A😀".to_owned(),
+                        raw: r#"\n\r\t\\\"\'\0\x41\u{1F600}"#.to_owned(),
+                    })),
                 },
                 Token{
-                    position: Position::new(0, 31, 31),
-                    kind: TokenKind::StringLiteral("😀".to_owned()),
+                    position: Position::new(0, 44, 44),
+                    end: Position{lineno: 0, column: 47, utf16_column: 48, offset: 50},
+                    kind: TokenKind::StringLiteral(Box::new("😀".into())),
                 },
                 Token{
-                    position: Position::new(0, 35, 35),
-                    kind: TokenKind::StringLiteral("".to_owned()),
+                    // '😀' is one char, but two UTF-16 code units and four bytes
+                    position: Position{lineno: 0, column: 48, utf16_column: 49, offset: 51},
+                    end: Position{lineno: 0, column: 50, utf16_column: 51, offset: 53},
+                    kind: TokenKind::StringLiteral(Box::new("".into())),
                 },
             ].into_iter()
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_escapes() {
+        let test_cases = vec![
+            (r#""ab\qc""#, r"[1,4] Unknown escape sequence '\q'"),
+            (r#""\x4""#, r"[1,2] Expected two hex digits after '\x'"),
+            (r#""\xe9""#, r"[1,2] '\xe9' is not an ASCII char: use '\u{e9}' instead"),
+            (r#""\u41""#, r"[1,2] Expected '{' after '\u'"),
+            (r#""\u{}""#, r"[1,2] '\u{...}' must contain between 1 and 6 hex digits"),
+            (r#""\u{1234567}""#, r"[1,2] '\u{...}' must contain between 1 and 6 hex digits"),
+            (r#""\u{D800}""#, r"[1,2] '\u{D800}' is not a valid char"),
+            (r#"x = "abc"#, r"[1,5] Unexpected end of string"),
+            (r#"x = "abc\"#, r"[1,5] Unexpected end of string"),
+        ];
+        for (input, expected) in test_cases {
+            let message = crate::lexer::lex(input).err().map(|err| err.to_string());
+            assert_eq!(message.as_deref(), Some(expected), "Lexing {}", input);
+        }
+    }
+}
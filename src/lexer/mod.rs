@@ -7,4 +7,4 @@ mod lex_strings;
 #[cfg(test)]
 mod test_utils;
 
-pub use self::core::lex;
\ No newline at end of file
+pub use self::core::{lex, lex_with_recovery, LexerError};
\ No newline at end of file
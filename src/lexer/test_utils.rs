@@ -13,13 +13,17 @@ pub fn lexer_test<T: AsRef<Token>>(
     for (i, expected) in expected_tokens.enumerate() {
         let actual = lexer.next_token()?;
         assert_eq!(&actual, expected.as_ref(), "Mismatch at token index {}", i);
-        last_position = expected.as_ref().position;
+        last_position = expected.as_ref().end;
     }
 
-    let length = input.chars().count();
+    let mut end_position = last_position;
+    for c in input.get(last_position.offset..).unwrap_or_default().chars() {
+        end_position.advance(c);
+    }
     let expected_last_token = Token{
         kind: TokenKind::EndOfFile,
-        position: last_position.add_horizontal(length - last_position.offset),
+        position: end_position,
+        end: end_position,
     };
 
     assert_eq!(lexer.next_token()?, expected_last_token, "Mismatch at end");
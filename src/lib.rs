@@ -5,6 +5,7 @@
 #![allow(clippy::redundant_field_names)]
 #![allow(clippy::wildcard_imports)]
 #![allow(clippy::missing_errors_doc)]
+#![allow(clippy::result_large_err)]
 
 // Items from clippy::restriction
 #![warn(clippy::as_conversions)]
@@ -48,11 +49,13 @@ pub mod values;
 pub mod tokens;
 pub mod ast;
 pub mod prettyprint;
+pub mod diagnostics;
+pub mod cst;
 
 mod lexer;
 mod parser;
 mod string_utils;
 //pub mod sorted_lookup;
 
-pub use lexer::lex;
-pub use parser::parse;
\ No newline at end of file
+pub use lexer::{lex, lex_with_recovery, LexerError};
+pub use parser::{parse, ParserError};
\ No newline at end of file
@@ -1,9 +1,52 @@
-use anyhow::Result;
-use impo::{lex, parse};
+use std::io::IsTerminal;
+
+use anyhow::{bail, Context, Result};
+use impo::cst::parse_cst;
+use impo::diagnostics::{collect_diagnostics, fix, to_json, Renderer};
+
+const USAGE: &str = "usage: impo [--json] [--fix] <path>";
 
 fn main() -> Result<()> {
-    let tokens = lex("# test")?;
-    let program = parse(&tokens)?;
+    let mut json = false;
+    let mut apply_fixes = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--fix" => apply_fixes = true,
+            _ if arg.starts_with('-') || path.is_some() => bail!(USAGE),
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else { bail!(USAGE) };
+    let mut source = std::fs::read_to_string(&path).with_context(|| format!("Could not read '{}'", path))?;
+
+    // Fixes are written back to the file, and we carry on to report
+    // whatever problems are left.
+    if apply_fixes {
+        let fixed = fix(&source);
+        if fixed != source {
+            std::fs::write(&path, &fixed).with_context(|| format!("Could not write '{}'", path))?;
+            source = fixed;
+        }
+    }
+
+    let (cst, lexer_errors) = parse_cst(&source);
+    let (program, parser_errors) = cst.to_ast();
+    let diagnostics = collect_diagnostics(&lexer_errors, &parser_errors);
+
+    // In JSON mode, stdout only ever contains the diagnostics.
+    if json {
+        println!("{}", to_json(&diagnostics));
+        return Ok(());
+    }
+
+    let renderer = Renderer::new(&source)
+        .with_path(&path)
+        .with_color(std::io::stderr().is_terminal());
+    for diagnostic in &diagnostics {
+        eprint!("{}", renderer.render(diagnostic));
+    }
     for stmt in &program.body {
         println!("{:?}", stmt);
     }
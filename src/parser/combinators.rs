@@ -1,5 +1,6 @@
 use nom::error as nom_error;
 pub use crate::tokens::{Position, Token, TokenKind};
+use super::core::{ParseResult, ParserError, track_failures};
 
 pub fn map_into<I, O1, O2, O3, E, F, G>(
     mut parser: F, 
@@ -119,6 +120,47 @@ where
     )
 }
 
+// If the parser fails without getting past the first token, reports that
+// we expected 'what' there instead of listing every token the parser
+// would have accepted.
+pub fn expecting<'a, O, P>(
+    what: &'static str,
+    mut parser: P,
+) -> impl FnMut(&'a [Token]) -> ParseResult<'a, O>
+where
+    P: nom::Parser<&'a [Token], O, ParserError>,
+{
+    move |tokens: &'a [Token]| {
+        let start = tokens.first().map(|token| token.position);
+        let relabel = |err: ParserError| {
+            if err.start() == start {
+                err.with_expected(vec![what.to_owned()])
+            } else {
+                err
+            }
+        };
+        let (result, _) = track_failures(&mut parser, tokens, |err| Some(relabel(err)));
+        result.map_err(|err| err.map(relabel))
+    }
+}
+
+// For optional parsers that continue what came before, such as an infix
+// operator after an operand. If the parser fails without getting past the
+// first token, the failure isn't worth reporting: whatever comes next
+// will report something more relevant.
+pub fn quiet<'a, O, P>(mut parser: P) -> impl FnMut(&'a [Token]) -> ParseResult<'a, O>
+where
+    P: nom::Parser<&'a [Token], O, ParserError>,
+{
+    move |tokens: &'a [Token]| {
+        let start = tokens.first().map(|token| token.position);
+        let (result, _) = track_failures(&mut parser, tokens, |err| {
+            if err.start() == start { None } else { Some(err) }
+        });
+        result
+    }
+}
+
 #[allow(dead_code)]
 pub fn debug<I, T, E>(
     message: &'static str,
@@ -1,19 +1,62 @@
+use std::cell::RefCell;
 use std::fmt;
-use std::cmp::{min, max};
 use nom::error as nom_error;
 
+pub use crate::diagnostics::{ErrorCode, Fix};
 pub use crate::tokens::{Position, Token, TokenKind};
 
 pub type ParseResult<'a, R> = nom::IResult<&'a [Token], R, ParserError>;
 
-#[derive(thiserror::Error)]
+#[derive(thiserror::Error, Clone)]
 pub struct ParserError {
+    pub code: ErrorCode,
     pub span: Option<(Position, Position)>,
     pub message: String,
     pub source: Option<Box<ParserError>>,
+    // Set if the error was caused by an unexpected token. The message is
+    // generated from this.
+    pub expected: Option<Box<Expected>>,
+    // Set if there's an obvious way to fix the error.
+    pub fix: Option<Box<Fix>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Expected {
+    // Descriptions of what would have been accepted instead.
+    pub items: Vec<String>,
+    pub found: String,
+    // What the expected items come after, such as "`while` condition".
+    pub context: Option<&'static str>,
+}
+
+impl Expected {
+    fn message(&self) -> String {
+        let context = self.context.map(|ctx| format!(" after {ctx}")).unwrap_or_default();
+        let items = match self.items.as_slice() {
+            [] => return format!("Unexpected {}{context}", self.found),
+            [item] => item.clone(),
+            [item1, item2] => format!("{item1} or {item2}"),
+            // Long lists aren't much help, and usually mean we don't know
+            // what the user was trying to write.
+            items if items.len() > 6 => return format!("Unexpected {}{context}", self.found),
+            [init @ .., last] => format!("{}, or {last}", init.join(", ")),
+        };
+        format!("Expected {items}{context}, found {}", self.found)
+    }
 }
 
 impl ParserError {
+    fn from_expected(span: Option<(Position, Position)>, expected: Expected) -> ParserError {
+        ParserError{
+            code: ErrorCode::UnexpectedToken,
+            span: span,
+            message: expected.message(),
+            source: None,
+            expected: Some(Box::new(expected)),
+            fix: None,
+        }
+    }
+
     fn error_lines(&self) -> Vec<String> {
         let mut out = Vec::new();
         let mut curr = self;
@@ -34,11 +77,74 @@ impl ParserError {
         out
     }
 
-    fn add_context(self, ctx: &'static str) -> Self {
-        ParserError {
-            span: self.span,
-            message: format!("{}: {}", ctx, self.message),
-            source: self.source,
+    #[must_use]
+    pub fn start(&self) -> Option<Position> {
+        self.span.map(|(start, _)| start)
+    }
+
+    // Errors without a span happened at the end of the file, so come
+    // after every other error.
+    fn offset(&self) -> usize {
+        self.start().map_or(usize::MAX, |start| start.offset)
+    }
+
+    #[must_use]
+    pub fn with_expected(self, items: Vec<String>) -> ParserError {
+        match self.expected {
+            Some(expected) => ParserError{
+                fix: self.fix,
+                ..ParserError::from_expected(self.span, Expected{items, ..*expected})
+            },
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn with_context(self, ctx: &'static str) -> ParserError {
+        match self.expected {
+            Some(expected) if expected.context.is_none() => ParserError{
+                fix: self.fix,
+                ..ParserError::from_expected(self.span, Expected{context: Some(ctx), ..*expected})
+            },
+            expected => ParserError{expected, ..self},
+        }
+    }
+
+    #[must_use]
+    pub fn with_fix(self, fix: Option<Fix>) -> ParserError {
+        ParserError{fix: fix.map(Box::new), ..self}
+    }
+
+    // Picks whichever error got further. If both failed at the same
+    // token, the errors are combined so the message lists everything
+    // that would have been accepted there.
+    #[must_use]
+    pub fn merge(mut self, mut other: ParserError) -> ParserError {
+        if self.offset() != other.offset() {
+            return if self.offset() > other.offset() { self } else { other };
+        }
+        let fix = self.fix.take().or_else(|| other.fix.take());
+        ParserError{fix, ..self.merge_expected(other)}
+    }
+
+    fn merge_expected(self, other: ParserError) -> ParserError {
+        match (self.expected, other.expected) {
+            (Some(mut left), Some(right)) => {
+                // Errors that know what they come after are more precise
+                // than ones that don't.
+                if left.context.is_some() != right.context.is_some() {
+                    let best = if left.context.is_some() { left } else { right };
+                    return ParserError::from_expected(self.span, *best);
+                }
+                for item in right.items {
+                    if !left.items.contains(&item) {
+                        left.items.push(item);
+                    }
+                }
+                ParserError::from_expected(self.span, *left)
+            },
+            (Some(left), None) => ParserError{expected: Some(left), ..self},
+            (None, right) => ParserError{expected: right, ..other},
         }
     }
 }
@@ -62,37 +168,70 @@ impl fmt::Display for ParserError {
 impl nom_error::ParseError<&[Token]> for ParserError {
     fn from_error_kind(input: &[Token], kind: nom_error::ErrorKind) -> Self {
         ParserError { 
+            code: ErrorCode::UnexpectedToken,
             span: input.get(0).map(Token::span),
             message: format!("error from {}", kind.description()),
             source: None,
+            expected: None,
+            fix: None,
         }
     }
 
-    fn append(input: &[Token], kind: nom_error::ErrorKind, other: Self) -> Self {
-        ParserError { 
-            span: compute_span(input, other.span),
-            message: format!("error from {}", kind.description()),
-            source: Some(Box::new(other)),
-        }
+    // The error from the inner parser is more useful than knowing which
+    // combinator it passed through.
+    fn append(_input: &[Token], _kind: nom_error::ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        self.merge(other)
     }
 }
 
 impl nom_error::ContextError<&[Token]> for ParserError {
     fn add_context(_input: &[Token], ctx: &'static str, other: Self) -> Self {
-        other.add_context(ctx)
+        other.with_context(ctx)
     }
 }
 
-fn compute_span(input: &[Token], existing_span: Option<(Position, Position)>) -> Option<(Position, Position)> {
-    input.get(0)
-        .map(Token::span)
-        .map(|(start, end)| {
-            if let Some((other_start, other_end)) = existing_span {
-                (min(start, other_start), max(end, other_end))
-            } else {
-                (start, end)
-            }
-        })
+thread_local! {
+    // The furthest any parser got before failing to match a token. Since
+    // combinators like 'opt' and 'many0' discard the errors of the parsers
+    // they wrap, the error a parser returns is often not where the real
+    // problem is, so we keep track of it separately.
+    static FURTHEST_FAILURE: RefCell<Option<ParserError>> = const { RefCell::new(None) };
+}
+
+fn record_failure(err: &ParserError) {
+    FURTHEST_FAILURE.with(|cell| {
+        let mut furthest = cell.borrow_mut();
+        *furthest = Some(match furthest.take() {
+            Some(prev) => prev.merge(err.clone()),
+            None => err.clone(),
+        });
+    });
+}
+
+pub fn reset_failures() {
+    FURTHEST_FAILURE.with(|cell| cell.borrow_mut().take());
+}
+
+// Runs the parser, and returns its result along with the furthest failure
+// recorded while it ran. That failure is passed through 'adjust' before
+// it's recorded for any enclosing parsers to see, which may also drop it.
+pub fn track_failures<'a, O>(
+    parser: &mut impl nom::Parser<&'a [Token], O, ParserError>,
+    tokens: &'a [Token],
+    adjust: impl Fn(ParserError) -> Option<ParserError>,
+) -> (ParseResult<'a, O>, Option<ParserError>) {
+    let outer = FURTHEST_FAILURE.with(|cell| cell.borrow_mut().take());
+    let result = parser.parse(tokens);
+    let inner = FURTHEST_FAILURE.with(|cell| cell.borrow_mut().take()).and_then(adjust);
+    FURTHEST_FAILURE.with(|cell| *cell.borrow_mut() = outer);
+    if let Some(err) = &inner {
+        record_failure(err);
+    }
+    (result, inner)
 }
 
 pub fn get_next(
@@ -106,36 +245,81 @@ pub fn get_next(
 }
 
 pub fn err_unexpected_eof(target: impl AsRef<str>) -> nom::Err<ParserError> {
-    nom::Err::Error(ParserError{
-        span: None,
-        message: format!("Unexpected EOF, looking for {}", target.as_ref()),
-        source: None,
-    })
+    err_expected(None, target.as_ref(), "end of file".to_owned())
 }
 
 pub fn err_unexpected_token(token: &Token) -> nom::Err<ParserError> {
-    nom::Err::Error(ParserError{
-        span: Some(token.span()),
-        message: format!("Unexpected token '{}', expected EOF", token.kind.name()),
-        source: None,
-    })
+    err_bad_match("end of file", token)
 }
 
 pub fn err_bad_match(expected: &str, actual: &Token) -> nom::Err<ParserError> {
-    nom::Err::Error(ParserError{
-        span: Some(actual.span()),
-        message: format!("Expected {}, got {}", expected, actual.kind.name()),
+    err_expected(Some(actual.span()), expected, actual.kind.describe())
+}
+
+// Like 'err_bad_match', but for when any of the given tokens would have
+// been accepted after the described part of the code. 'tokens' starts
+// with the token we found instead.
+pub fn err_bad_match_after(expected: &[TokenKind], after: &'static str, tokens: &[Token]) -> nom::Err<ParserError> {
+    err_bad_match_kinds(expected, Some(after), tokens)
+}
+
+fn err_bad_match_kinds(expected: &[TokenKind], after: Option<&'static str>, tokens: &[Token]) -> nom::Err<ParserError> {
+    let Some(actual) = tokens.first() else {
+        return err_unexpected_eof(expected.first().map(TokenKind::describe).unwrap_or_default());
+    };
+    let err = ParserError::from_expected(Some(actual.span()), Expected{
+        items: expected.iter().map(TokenKind::describe).collect(),
+        found: actual.kind.describe(),
+        context: after,
+    });
+    let err = err.with_fix(suggest_missing_colon(expected, tokens));
+    record_failure(&err);
+    nom::Err::Error(err)
+}
+
+// Blocks must come after a `:`, which is easy to forget. If a block
+// starts where one was expected, we can add it back in.
+fn suggest_missing_colon(expected: &[TokenKind], tokens: &[Token]) -> Option<Fix> {
+    match tokens {
+        [newline, indent, ..] if expected.contains(&TokenKind::Colon)
+            && newline.kind == TokenKind::Newline
+            && indent.kind == TokenKind::Indent => {
+            Some(Fix::insert(newline.position, ":", "add a `:` before the block"))
+        },
+        _ => None,
+    }
+}
+
+fn err_expected(span: Option<(Position, Position)>, expected: &str, found: String) -> nom::Err<ParserError> {
+    let err = ParserError::from_expected(span, Expected{
+        items: vec![expected.to_owned()],
+        found: found,
+        context: None,
+    });
+    record_failure(&err);
+    nom::Err::Error(err)
+}
+
+// Unlike the other errors, failures stop any enclosing alternatives
+// from being tried.
+pub fn err_failure(code: ErrorCode, span: Option<(Position, Position)>, message: impl Into<String>) -> nom::Err<ParserError> {
+    nom::Err::Failure(ParserError{
+        code: code,
+        span: span,
+        message: message.into(),
         source: None,
+        expected: None,
+        fix: None,
     })
 }
 
 impl<'a> nom::Parser<&'a [Token], &'a Token, ParserError> for TokenKind {
     fn parse(&mut self, tokens: &'a [Token]) -> ParseResult<'a, &'a Token> {
-        let (rest, token) = get_next(tokens, self.name())?;
+        let (rest, token) = get_next(tokens, self.describe())?;
         if &token.kind == self {
             Ok((rest, token))
         } else {
-            Err(err_bad_match(self.name(), token))
+            Err(err_bad_match_kinds(std::slice::from_ref(self), None, tokens))
         }
     }
 }
@@ -3,8 +3,10 @@ mod combinators;
 mod parse_stmt;
 mod parse_type;
 mod parse_expr;
+mod recovery;
 
 pub use parse_stmt::parse;
+pub use self::core::ParserError;
 
 #[cfg(test)]
 mod test_utils;
\ No newline at end of file
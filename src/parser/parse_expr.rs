@@ -1,55 +1,62 @@
 use nom::branch::alt;
-use nom::combinator::{map, opt};
-use nom::error::context;
-use nom::multi::{many1, separated_list0, separated_list1};
-use nom::sequence::{delimited, pair, preceded};
+use nom::combinator::{map, opt, peek};
+use nom::multi::{many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated};
 use struple::Struple;
 
 use crate::ast::exprs::*;
 use crate::ast::primitives::*;
+use crate::ast::types::{FuncType, TypeNode};
 
 use super::core::*;
 use super::combinators::*;
+use super::parse_stmt::{match_indented_block, match_params, match_return_type};
+use super::parse_type::{match_throws, match_type};
 
 pub fn match_expr(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_expr", 
+    expecting(
+        "expression",
         alt((match_operations, match_group, match_tuple)),
     )(tokens)
 }
 
 fn match_group(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_group",
-        map(
-            delimited(
-                TokenKind::LParen,
-                match_expr,
-                TokenKind::RParen,
-            ),
-            |e| ExprNode::ExplicitParenthesis(Box::new(e)),
+    map(
+        delimited(
+            TokenKind::LParen,
+            match_expr,
+            TokenKind::RParen,
         ),
+        |e| ExprNode::ExplicitParenthesis(Box::new(e)),
     )(tokens)
 }
 
 fn match_tuple(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_tuple",
-        map_into(
-            delimited(
-                TokenKind::LParen,
-                separated_list1(
-                    TokenKind::Comma, 
-                    match_expr,
-                ),
-                TokenKind::RParen,
+    map_into(
+        delimited(
+            TokenKind::LParen,
+            separated_list1(
+                TokenKind::Comma, 
+                match_expr,
             ),
-            TupleExpr::new,
+            TokenKind::RParen,
         ),
+        TupleExpr::new,
     )(tokens)
 }
 
 fn match_operations(tokens: &[Token]) -> ParseResult<ExprNode> {
+    build_operations(tokens, 0)
+}
+
+// Matches an operation containing only operators that bind more tightly
+// than 'to', which lets callers use 'to' as a separator.
+pub fn match_range_bound(tokens: &[Token]) -> ParseResult<ExprNode> {
+    let (_, min_bp) = InfixOp::To.binding_power();
+    build_operations(tokens, min_bp)
+}
+
+fn build_operations(tokens: &[Token], min_bp: u8) -> ParseResult<ExprNode> {
     type BP = (u8, u8);
 
     fn merge(prev: (BP, ExprNode), op: InfixOp, next: (BP, ExprNode)) -> (BP, ExprNode) {
@@ -80,31 +87,31 @@ fn match_operations(tokens: &[Token]) -> ParseResult<ExprNode> {
         (next_bp, new_infix.into())
     }
 
-    fn build(tokens: &[Token], min_bp: u8) -> ParseResult<ExprNode> {
-        let mut rest = tokens;
-
-        let (rest_prefix, op_prefix) = opt(match_prefix_op)(rest)?;
+    fn build_head(tokens: &[Token]) -> ParseResult<ExprNode> {
+        let (rest_prefix, op_prefix) = opt(match_prefix_op)(tokens)?;
 
-        let (rest_head, subexpr_head) = match op_prefix {
+        match op_prefix {
             Some(op) => {
-                rest = rest_prefix;
-
                 let ((), right_bp) = op.binding_power();
-                let (rest_prefix_expr, subexpr_prefix_expr) = build(rest, right_bp)?;
+                let (rest_prefix_expr, subexpr_prefix_expr) = build(rest_prefix, right_bp)?;
                 let curr = PrefixExpr{
                     op: op,
                     expr: subexpr_prefix_expr,
                 };
-                (rest_prefix_expr, curr.into())
+                Ok((rest_prefix_expr, curr.into()))
             },
-            None => match_operand(rest)?,
-        };
+            None => match_operand(tokens),
+        }
+    }
+
+    fn build(tokens: &[Token], min_bp: u8) -> ParseResult<ExprNode> {
+        let (rest_head, subexpr_head) = expecting("expression", build_head)(tokens)?;
 
         let mut curr = ((0, 0), subexpr_head);
-        rest = rest_head;
+        let mut rest = rest_head;
         
         loop {
-            let (rest_op, op) = opt(match_infix_op)(rest)?;
+            let (rest_op, op) = opt(quiet(match_infix_op))(rest)?;
 
             let op = match op {
                 Some(op) => op,
@@ -126,10 +133,7 @@ fn match_operations(tokens: &[Token]) -> ParseResult<ExprNode> {
         Ok((rest, curr.1))
     }
 
-    context(
-        "match_operations",
-        |tokens| build(tokens, 0),
-    )(tokens)
+    build(tokens, min_bp)
 }
 
 fn match_infix_op(tokens: &[Token]) -> ParseResult<InfixOp> {
@@ -184,21 +188,19 @@ fn match_prefix_op(tokens: &[Token]) -> ParseResult<PrefixOp> {
 
 // Operand -- anything that's a valid operand.
 fn match_operand(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_operand", 
-        alt((match_call_like, match_unit)),
-    )(tokens)
+    alt((match_call_like, match_unit))(tokens)
 }
 
 fn match_call_like(tokens: &[Token]) -> ParseResult<ExprNode> {
     enum Tail {
         FuncCall(Vec<ExprNode>),
+        TypeApplication(Vec<TypeNode>),
         Index(ExprNode),
     }
 
     fold1(
         match_unit,
-        alt((
+        quiet(alt((
             map(
                 delimited(
                     TokenKind::LParen,
@@ -210,6 +212,10 @@ fn match_call_like(tokens: &[Token]) -> ParseResult<ExprNode> {
                 ),
                 Tail::FuncCall,
             ),
+            map(
+                match_type_args,
+                Tail::TypeApplication,
+            ),
             map(
             delimited(
                     TokenKind::LSquare,
@@ -218,13 +224,17 @@ fn match_call_like(tokens: &[Token]) -> ParseResult<ExprNode> {
                 ),
                 Tail::Index,
             )
-        )),
+        ))),
         |curr, tail| {
             match tail {
                 Tail::FuncCall(params) => FuncCallExpr{
                     func: curr,
                     params: params,
                 }.into(),
+                Tail::TypeApplication(type_args) => TypeApplicationExpr{
+                    source: curr,
+                    type_args: type_args,
+                }.into(),
                 Tail::Index(index) => IndexExpr{
                     source: curr,
                     index: index,
@@ -234,36 +244,144 @@ fn match_call_like(tokens: &[Token]) -> ParseResult<ExprNode> {
     )(tokens)
 }
 
+// Both indexing and explicit type args use square brackets. We treat
+// the brackets as type args if they are immediately followed by a call
+// and everything inside parses as a type, so `funcs[i](x)` is parsed as
+// a type application too. Once the whole program is parsed,
+// `resolve_type_args` turns it back into an index if `i` is a variable.
+fn match_type_args(tokens: &[Token]) -> ParseResult<Vec<TypeNode>> {
+    terminated(
+        delimited(
+            TokenKind::LSquare,
+            separated_list1(TokenKind::Comma, match_type),
+            TokenKind::RSquare,
+        ),
+        peek(TokenKind::LParen),
+    )(tokens)
+}
+
 // Unit -- An entity that is either indivisible or consists of several
 // pieces, where not all of the pieces are valid expressions.
 fn match_unit(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_unit", 
-        alt((match_field_lookup, match_atom)),
-    )(tokens)
+    alt((match_field_lookup, match_atom))(tokens)
 }
 
 fn match_field_lookup(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_lookup",
-        map_into(
-            pair(
-                match_atom, 
-                many1(preceded(
-                    TokenKind::Dot, 
-                    match_name,
-                )),
-            ),
-            FieldLookupExpr::from_tuple,
-        )
+    enum Field {
+        Name(Name),
+        Index(usize),
+    }
+
+    // Consecutive lookups of the same kind are grouped into a single
+    // node, so `a.b.c.0.1` becomes a tuple lookup on a field lookup.
+    fold1(
+        match_atom,
+        quiet(preceded(
+            TokenKind::Dot,
+            alt((
+                map(match_name, Field::Name),
+                map(match_tuple_index, Field::Index),
+            )),
+        )),
+        |curr, field| {
+            match (curr, field) {
+                (ExprNode::FieldLookup(mut e), Field::Name(name)) => {
+                    e.name_chain.push(name);
+                    ExprNode::FieldLookup(e)
+                },
+                (ExprNode::TupleLookup(mut e), Field::Index(index)) => {
+                    e.index_chain.push(index);
+                    ExprNode::TupleLookup(e)
+                },
+                (curr, Field::Name(name)) => FieldLookupExpr{
+                    source: curr,
+                    name_chain: vec![name],
+                }.into(),
+                (curr, Field::Index(index)) => TupleLookupExpr{
+                    source: curr,
+                    index_chain: vec![index],
+                }.into(),
+            }
+        }
     )(tokens)
 }
 
+fn match_tuple_index(tokens: &[Token]) -> ParseResult<usize> {
+    let (rest, token) = get_next(tokens, "tuple index")?;
+    match &token.kind {
+        TokenKind::IntLiteral(lit) if lit.base == 10 => {
+            Ok((rest, lit.raw_value))
+        },
+        _ => {
+            Err(err_bad_match("tuple index", token))
+        }
+    }
+}
+
 // Atom -- a small, indivisible unit
 fn match_atom(tokens: &[Token]) -> ParseResult<ExprNode> {
-    context(
-        "match_atom",
-        alt((match_variable, match_literal, match_group)),
+    alt((match_variable, match_literal, match_group, match_array, match_lambda))(tokens)
+}
+
+// Lambdas used within an expression must have an expression body, such as
+// `fn(x: Int) -> Int: x + 1`. The body extends as far right as possible.
+fn match_lambda(tokens: &[Token]) -> ParseResult<ExprNode> {
+    map(
+        pair(
+            terminated(match_lambda_header, TokenKind::Colon),
+            match_expr,
+        ),
+        |((param_names, signature), body)| {
+            LambdaExpr::new(param_names, signature, LambdaBody::Expr(body)).into()
+        },
+    )(tokens)
+}
+
+// Lambdas with an indented block body are only allowed as the value of a
+// statement, such as a variable declaration, since the body must start on
+// a new line. The block also ends the statement, so the caller shouldn't
+// look for a trailing newline.
+pub fn match_block_lambda(tokens: &[Token]) -> ParseResult<ExprNode> {
+    map(
+        pair(match_lambda_header, match_indented_block),
+        |((param_names, signature), body)| {
+            LambdaExpr::new(param_names, signature, LambdaBody::Block(body)).into()
+        },
+    )(tokens)
+}
+
+// Unlike functions, lambdas must always give their return type, even
+// if it's just `-> ()`.
+fn match_lambda_header(tokens: &[Token]) -> ParseResult<(Vec<Name>, FuncType)> {
+    let (rest, params) = preceded(TokenKind::Fn, match_params)(tokens)?;
+    if peek(TokenKind::Arrow)(rest).is_err() {
+        let header_end = tokens.get(tokens.len() - rest.len() - 1);
+        let span = tokens.first().zip(header_end).map(|(first, last)| (first.position, last.end));
+        return Err(err_failure(
+            ErrorCode::MissingReturnType,
+            span,
+            "A lambda must declare its return type, such as '-> ()' if it returns nothing",
+        ));
+    }
+    let (rest, (return_type, throws)) = pair(match_return_type, match_throws)(rest)?;
+    let (param_names, param_types) = params.into_iter().unzip();
+    Ok((rest, (param_names, FuncType{
+        typevars: Vec::new(),
+        param_types: param_types,
+        return_type: return_type,
+        throws: throws,
+    })))
+}
+
+fn match_array(tokens: &[Token]) -> ParseResult<ExprNode> {
+    map_into(
+        delimited_list(
+            TokenKind::LSquare,
+            TokenKind::Comma,
+            match_expr,
+            TokenKind::RSquare,
+        ),
+        |items| ArrayExpr{ items: items },
     )(tokens)
 }
 
@@ -283,13 +401,23 @@ pub fn match_name(tokens: &[Token]) -> ParseResult<Name> {
     }
 }
 
+pub fn match_identifier(tokens: &[Token]) -> ParseResult<Identifier> {
+    map(
+        pair(
+            many0(terminated(match_name, TokenKind::Dot)),
+            match_name,
+        ),
+        Identifier::from_tuple,
+    )(tokens)
+}
+
 fn match_literal(tokens: &[Token]) -> ParseResult<ExprNode> {
     let (rest, token) = get_next(tokens, "literal")?;
     let output = match &token.kind {
         TokenKind::BoolLiteral(lit) => ExprNode::BoolLiteral(*lit),
         TokenKind::IntLiteral(lit) => lit.clone().into(),
         TokenKind::FloatLiteral(lit) => lit.clone().into(),
-        TokenKind::StringLiteral(lit) => lit.clone().into(),
+        TokenKind::StringLiteral(lit) => ExprNode::StringLiteral(lit.clone()),
         _ => {
             return Err(err_bad_match("literal", token));
         }
@@ -400,7 +528,7 @@ mod tests {
                 fractional_digits: "567".into(),
                 power: "".into(),
             }),
-            TokenKind::StringLiteral("foo".into()),
+            TokenKind::StringLiteral(Box::new("foo".into())),
             TokenKind::BoolLiteral(true),
         ];
 
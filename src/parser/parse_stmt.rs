@@ -4,34 +4,137 @@ use nom::multi::{many0, many1, fold_many0, separated_list1};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 use struple::Struple;
 
-use crate::ast::{FuncType, stmts::*};
+use crate::ast::{ErrorExpr, ExprNode, FuncType, Identifier, InfixOp, Name, resolve_type_args, stmts::*};
 use crate::ast::types::TypeNode;
+use crate::ast::visit::Visitor;
 
 use super::core::*;
 use super::combinators::*;
-use super::parse_expr::{match_expr, match_name};
-use super::parse_type::match_type;
+use super::parse_expr::{match_block_lambda, match_expr, match_identifier, match_name, match_range_bound};
+use super::parse_type::{match_reference_type, match_throws, match_type};
+use super::recovery::{collect_errors, recover_expr, recover_member, recover_stmt, recover_type};
 
-pub fn parse(tokens: &[Token]) -> Result<Program, nom::Err<ParserError>> {
-    let (rest, out) = complete(match_program)(tokens)?;
-    rest.first().map_or(
-        Ok(out),
-        |extra_token| Err(err_unexpected_token(extra_token)),
-    )
+// Parses the program, recovering from any errors. Statements, expressions,
+// and types that couldn't be parsed are replaced by error nodes, so this
+// always returns a (possibly partial) program along with every error found.
+pub fn parse(tokens: &[Token]) -> (Program, Vec<ParserError>) {
+    reset_failures();
+    let (rest, mut program) = match complete(match_program)(tokens) {
+        Ok(out) => out,
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+            return (Program{body: Vec::new()}, vec![err]);
+        },
+        Err(nom::Err::Incomplete(_)) => {
+            return (Program{body: Vec::new()}, vec![make_incomplete_err()]);
+        },
+    };
+    let mut errors = collect_errors(&program);
+    if let Some(extra_token) = rest.first() {
+        if let nom::Err::Error(err) = err_unexpected_token(extra_token) {
+            errors.push(err);
+        }
+    }
+    errors.extend(check_loop_exits(&program));
+    resolve_type_args(&mut program);
+
+    // The lexer already reported why it produced any error tokens
+    let error_tokens: Vec<Position> = tokens.iter()
+        .filter(|token| token.kind == TokenKind::Error)
+        .map(|token| token.position)
+        .collect();
+    errors.retain(|err| !err.start().is_some_and(|start| error_tokens.contains(&start)));
+    (program, errors)
+}
+
+// Checks that every break and continue is nested inside a loop body
+// belonging to the same function or lambda.
+fn check_loop_exits(program: &Program) -> Vec<ParserError> {
+    let mut checker = LoopExitChecker{in_loop: false, errors: Vec::new()};
+    checker.visit_block(&program.body);
+    checker.errors
+}
+
+struct LoopExitChecker {
+    in_loop: bool,
+    errors: Vec<ParserError>,
+}
+
+impl LoopExitChecker {
+    fn visit_with_in_loop(&mut self, in_loop: bool, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.in_loop, in_loop);
+        visit(self);
+        self.in_loop = outer;
+    }
+}
+
+impl Visitor for LoopExitChecker {
+    fn visit_function(&mut self, function: &FuncImplementationDefStmt) {
+        self.visit_with_in_loop(false, |checker| checker.walk_function(function));
+    }
+
+    fn visit_stmt(&mut self, stmt: &StmtNode) {
+        match stmt {
+            StmtNode::Break(s) if !self.in_loop => {
+                self.errors.push(make_loop_exit_outside_loop_err("break", s.span));
+            },
+            StmtNode::Continue(s) if !self.in_loop => {
+                self.errors.push(make_loop_exit_outside_loop_err("continue", s.span));
+            },
+            StmtNode::For(_) | StmtNode::Foreach(_) | StmtNode::While(_) => {
+                self.visit_with_in_loop(true, |checker| checker.walk_stmt(stmt));
+            },
+            _ => self.walk_stmt(stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &ExprNode) {
+        match expr {
+            ExprNode::Lambda(_) => self.visit_with_in_loop(false, |checker| checker.walk_expr(expr)),
+            _ => self.walk_expr(expr),
+        }
+    }
+}
+
+fn make_incomplete_err() -> ParserError {
+    ParserError{
+        code: ErrorCode::InternalParserError,
+        span: None,
+        message: "Unexpected fatal error: parser requested more input".to_owned(),
+        source: None,
+        expected: None,
+        fix: None,
+    }
+}
+
+fn make_loop_exit_outside_loop_err(keyword: &str, span: (Position, Position)) -> ParserError {
+    ParserError{
+        code: ErrorCode::LoopExitOutsideLoop,
+        span: Some(span),
+        message: format!("'{keyword}' can only be used inside a while, for, or foreach loop"),
+        source: None,
+        expected: None,
+        fix: None,
+    }
 }
 
 fn match_program(tokens: &[Token]) -> ParseResult<Program> {
     map(
-        many0(match_stmt),
-        |body| Program{body: body},
+        pair(
+            many0(alt((match_import, match_empty_line))),
+            many0(recover_stmt(match_toplevel_stmt)),
+        ),
+        |(mut body, rest)| {
+            body.extend(rest);
+            Program{body: body}
+        },
     )(tokens)
 }
 
 fn match_block(tokens: &[Token]) -> ParseResult<Block> {
-    many1(match_stmt)(tokens)
+    many1(recover_stmt(match_stmt))(tokens)
 }
 
-fn match_indented_block(tokens: &[Token]) -> ParseResult<Block> {
+pub fn match_indented_block(tokens: &[Token]) -> ParseResult<Block> {
     delimited(
         tuple((TokenKind::Colon, TokenKind::Newline, TokenKind::Indent)),
         match_block,
@@ -39,30 +142,107 @@ fn match_indented_block(tokens: &[Token]) -> ParseResult<Block> {
     )(tokens)
 }
 
+// Statements that may only appear at module scope, on top of
+// everything allowed in nested blocks.
+fn match_toplevel_stmt(tokens: &[Token]) -> ParseResult<StmtNode> {
+    alt((
+        map(match_const_def, ConstDefStmt::into),
+        match_stmt,
+    ))(tokens)
+}
+
 fn match_stmt(tokens: &[Token]) -> ParseResult<StmtNode> {
     alt((
+        match_misplaced_import,
         match_func_signature_def,
         match_func_implementation_def,
+        match_interface_def,
+        match_class_def,
+        match_sentinal_def,
         match_while,
+        match_for,
         match_foreach,
         match_if,
+        match_match,
+        match_try,
+        match_break,
+        match_continue,
+        match_var_decl,
         match_assignment,
         match_line,
         match_return,
         match_panic,
+        match_throw,
         match_empty_line,
     ))(tokens)
 }
 
+fn match_import(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map(match_import_stmt, ImportStmt::into)(tokens)
+}
+
+fn match_import_stmt(tokens: &[Token]) -> ParseResult<ImportStmt> {
+    let match_imported_name = map(
+        pair(
+            match_name,
+            opt(preceded(TokenKind::As, match_name)),
+        ),
+        ImportedName::from_tuple,
+    );
+    map(
+        terminated(
+            tuple((
+                match_comment,
+                match_hints,
+                preceded(TokenKind::From, match_identifier),
+                preceded(
+                    TokenKind::Import,
+                    separated_list1(TokenKind::Comma, match_imported_name),
+                ),
+            )),
+            TokenKind::Newline,
+        ),
+        ImportStmt::from_tuple,
+    )(tokens)
+}
+
+// Imports are only matched by match_program, before any other statement.
+// Anything that would have been a valid import elsewhere is reported here
+// so the user gets a clearer error than a generic parse failure. Other
+// statements fail as soon as we see they don't start with `from`.
+fn match_misplaced_import(tokens: &[Token]) -> ParseResult<StmtNode> {
+    let (_, (_, _, from)) = tuple((match_comment, match_hints, TokenKind::From))(tokens)?;
+    let (_, import) = match_import_stmt(tokens)?;
+    Err(err_failure(
+        ErrorCode::MisplacedImport,
+        Some(from.span()),
+        format!(
+            "Import from '{}' must appear at the top of the file, before any other statement",
+            import.source,
+        ),
+    ))
+}
+
 fn match_func_signature_def(tokens: &[Token]) -> ParseResult<StmtNode> {
     map(
-        terminated(match_func_header, TokenKind::Newline),
+        match_func_signature,
         FuncSignatureDefStmt::into,
     )(tokens)
 }
 
+fn match_func_signature(tokens: &[Token]) -> ParseResult<FuncSignatureDefStmt> {
+    terminated(match_func_header, TokenKind::Newline)(tokens)
+}
+
 fn match_func_implementation_def(tokens: &[Token]) -> ParseResult<StmtNode> {
-    map_into(
+    map(
+        match_func_implementation,
+        FuncImplementationDefStmt::into,
+    )(tokens)
+}
+
+fn match_func_implementation(tokens: &[Token]) -> ParseResult<FuncImplementationDefStmt> {
+    map(
         tuple((
             match_func_header,
             match_indented_block,
@@ -72,50 +252,283 @@ fn match_func_implementation_def(tokens: &[Token]) -> ParseResult<StmtNode> {
 }
 
 fn match_func_header(tokens: &[Token]) -> ParseResult<FuncSignatureDefStmt> {
-    let match_type_vars = optional_delimited_list(
+    map(
+        tuple((
+            match_comment,
+            match_hints,
+            preceded(TokenKind::Fn, match_name),
+            match_type_vars,
+            match_params,
+            match_return_type,
+            match_throws,
+        )),
+        |(comment, hints, name, typevars, params, return_type, throws)| {
+            build_func_signature(comment, hints, name, typevars, params, return_type, throws)
+        }
+    )(tokens)
+}
+
+fn match_type_vars(tokens: &[Token]) -> ParseResult<Vec<Name>> {
+    optional_delimited_list(
         TokenKind::LSquare,
         TokenKind::Comma,
         match_name,
         TokenKind::RSquare,
-    );
-    let match_params = delimited_list(
+    )(tokens)
+}
+
+pub fn match_params(tokens: &[Token]) -> ParseResult<Vec<(Name, TypeNode)>> {
+    delimited_list(
         TokenKind::LParen,
         TokenKind::Comma,
         separated_pair(
             match_name,
             TokenKind::Colon,
-            match_type,
+            recover_type(match_type, "parameter type", &[TokenKind::Comma, TokenKind::RParen]),
         ),
         TokenKind::RParen,
-    );
-    let match_return = opt_or(
+    )(tokens)
+}
+
+pub fn match_return_type(tokens: &[Token]) -> ParseResult<TypeNode> {
+    opt_or(
         preceded(
             TokenKind::Arrow,
-            match_type,
+            recover_type(match_type, "return type", &[TokenKind::Colon, TokenKind::Newline, TokenKind::Throws]),
         ),
         |o| o.unwrap_or(TypeNode::Unit),
+    )(tokens)
+}
+
+fn build_func_signature(
+    comment: Comment,
+    hints: Vec<Hint>,
+    name: Name,
+    typevars: Vec<Name>,
+    params: Vec<(Name, TypeNode)>,
+    return_type: TypeNode,
+    throws: Vec<TypeNode>,
+) -> FuncSignatureDefStmt {
+    let (param_names, param_types) = params.into_iter().unzip();
+    FuncSignatureDefStmt{
+        comment: comment,
+        hints: hints,
+        name: name,
+        signature: FuncType{
+            typevars: typevars,
+            param_types: param_types,
+            return_type: return_type,
+            throws: throws,
+        },
+        param_names: param_names,
+    }
+}
+
+fn match_class_def(tokens: &[Token]) -> ParseResult<StmtNode> {
+    #[derive(Clone)]
+    enum Member {
+        Const(ConstDefStmt),
+        Field(FieldSignatureDefStmt),
+        Constructor(FuncImplementationDefStmt, (Position, Position)),
+        Method(FuncImplementationDefStmt),
+        EmptyLine,
+        Error(ErrorExpr),
+    }
+
+    let match_implements = opt_or(
+        preceded(
+            TokenKind::Implements,
+            separated_list1(TokenKind::Comma, match_reference_type),
+        ),
+        Option::unwrap_or_default,
     );
-    map(
+    let match_member = alt((
+        map(match_const_def, Member::Const),
+        map(match_field_signature, Member::Field),
+        map(match_constructor_def, |(constructor, span)| Member::Constructor(constructor, span)),
+        map(match_func_implementation, Member::Method),
+        value(Member::EmptyLine, TokenKind::Newline),
+    ));
+    let match_body = delimited(
+        tuple((TokenKind::Colon, TokenKind::Newline, TokenKind::Indent)),
+        many1(recover_member(match_member, Member::Error)),
+        TokenKind::Unindent,
+    );
+
+    let (rest, (comment, hints, name, typevars, implements, members)) = tuple((
+        match_comment,
+        match_hints,
+        preceded(TokenKind::Class, match_name),
+        match_type_vars,
+        match_implements,
+        match_body,
+    ))(tokens)?;
+
+    let mut class = ClassDefStmt{
+        comment: comment,
+        hints: hints,
+        identifier: Identifier::new(Vec::new(), name),
+        typevars: typevars,
+        implements: implements,
+        constants: Vec::new(),
+        fields: Vec::new(),
+        constructor: None,
+        functions: Vec::new(),
+        errors: Vec::new(),
+    };
+    for member in members {
+        match member {
+            Member::Const(constant) => class.constants.push(constant),
+            Member::Field(field) => class.fields.push(field),
+            // Only the first constructor is kept.
+            Member::Constructor(_, span) if class.constructor.is_some() => {
+                class.errors.push(ErrorExpr{
+                    code: ErrorCode::DuplicateConstructor,
+                    message: format!("Class '{}' defines more than one constructor", class.identifier),
+                    span: span,
+                    fix: None,
+                });
+            },
+            Member::Constructor(constructor, _) => class.constructor = Some(constructor),
+            Member::Method(method) => class.functions.push(method),
+            Member::EmptyLine => {},
+            Member::Error(error) => class.errors.push(error),
+        }
+    }
+    Ok((rest, class.into()))
+}
+
+fn match_interface_def(tokens: &[Token]) -> ParseResult<StmtNode> {
+    #[derive(Clone)]
+    enum Member {
+        Field(FieldSignatureDefStmt),
+        Function(FuncSignatureDefStmt),
+        EmptyLine,
+        Error(ErrorExpr),
+    }
+
+    let match_member = alt((
+        map(match_field_signature, Member::Field),
+        map(match_func_signature, Member::Function),
+        value(Member::EmptyLine, TokenKind::Newline),
+    ));
+    let match_body = delimited(
+        tuple((TokenKind::Colon, TokenKind::Newline, TokenKind::Indent)),
+        many1(recover_member(match_member, Member::Error)),
+        TokenKind::Unindent,
+    );
+
+    map_into(
         tuple((
             match_comment,
-            preceded(TokenKind::Fn, match_name),
+            match_hints,
+            preceded(TokenKind::Interface, match_name),
             match_type_vars,
-            match_params,
-            match_return,
+            match_body,
         )),
-        |(comment, name, typevars, params, return_type)| {
-            let (param_names, param_types) = params.into_iter().unzip();
-            FuncSignatureDefStmt{
+        |(comment, hints, name, typevars, members)| {
+            let mut interface = InterfaceDefStmt{
                 comment: comment,
-                name: name,
-                signature: FuncType{
-                    typevars: typevars,
-                    param_types: param_types,
-                    return_type: return_type,
-                },
-                param_names: param_names,
+                hints: hints,
+                identifier: Identifier::new(Vec::new(), name),
+                typevars: typevars,
+                fields: Vec::new(),
+                functions: Vec::new(),
+                errors: Vec::new(),
+            };
+            for member in members {
+                match member {
+                    Member::Field(field) => interface.fields.push(field),
+                    Member::Function(function) => interface.functions.push(function),
+                    Member::EmptyLine => {},
+                    Member::Error(error) => interface.errors.push(error),
+                }
             }
-        }
+            interface
+        },
+    )(tokens)
+}
+
+fn match_sentinal_def(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            delimited(TokenKind::Sentinal, match_name, TokenKind::Newline),
+        )),
+        |(comment, hints, name)| SentinalDefStmt{
+            comment: comment,
+            hints: hints,
+            identifier: Identifier::new(Vec::new(), name),
+        },
+    )(tokens)
+}
+
+fn match_const_def(tokens: &[Token]) -> ParseResult<ConstDefStmt> {
+    map(
+        tuple((
+            match_comment,
+            match_hints,
+            preceded(TokenKind::Const, match_name),
+            opt(preceded(TokenKind::Colon, match_type)),
+            preceded(TokenKind::Assign, match_stmt_value),
+        )),
+        ConstDefStmt::from_tuple,
+    )(tokens)
+}
+
+fn match_field_signature(tokens: &[Token]) -> ParseResult<FieldSignatureDefStmt> {
+    map(
+        terminated(
+            tuple((
+                match_comment,
+                match_hints,
+                match_name,
+                preceded(
+                    TokenKind::Colon,
+                    recover_type(match_type, "field type", &[TokenKind::Newline]),
+                ),
+            )),
+            TokenKind::Newline,
+        ),
+        |(comment, hints, name, typ)| FieldSignatureDefStmt{
+            comment: comment,
+            hints: hints,
+            identifier: Identifier::new(Vec::new(), name),
+            typ: typ,
+        },
+    )(tokens)
+}
+
+// Constructors may be written either as `fn constructor(...)` or as a
+// bare `constructor(...)`. They take no type vars and return nothing.
+// Also returns the span of the keywords, for reporting errors.
+fn match_constructor_def(tokens: &[Token]) -> ParseResult<(FuncImplementationDefStmt, (Position, Position))> {
+    map(
+        tuple((
+            match_comment,
+            match_hints,
+            pair(opt(TokenKind::Fn), TokenKind::Constructor),
+            match_params,
+            match_throws,
+            match_indented_block,
+        )),
+        |(comment, hints, (fn_token, constructor_token), params, throws, body)| {
+            let start = fn_token.unwrap_or(constructor_token).position;
+            let constructor = FuncImplementationDefStmt{
+                function: build_func_signature(
+                    comment,
+                    hints,
+                    "constructor".into(),
+                    Vec::new(),
+                    params,
+                    TypeNode::Unit,
+                    throws,
+                ),
+                body: body,
+            };
+            (constructor, (start, constructor_token.end))
+        },
     )(tokens)
 }
 
@@ -123,9 +536,10 @@ fn match_while(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
         tuple((
             match_comment,
+            match_hints,
             preceded(
                 TokenKind::While,
-                match_expr,
+                recover_expr(match_expr, "`while` condition", &[TokenKind::Colon]),
             ),
             match_indented_block,
         )),
@@ -133,13 +547,34 @@ fn match_while(tokens: &[Token]) -> ParseResult<StmtNode> {
     )(tokens)
 }
 
+fn match_for(tokens: &[Token]) -> ParseResult<StmtNode> {
+    let match_direction = alt((
+        value(ForDirection::Ascending, TokenKind::To),
+        value(ForDirection::Descending, TokenKind::DownTo),
+    ));
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            preceded(TokenKind::For, match_name),
+            preceded(TokenKind::From, match_range_bound),
+            match_direction,
+            match_range_bound,
+            opt(preceded(TokenKind::By, match_range_bound)),
+            match_indented_block,
+        )),
+        ForStmt::from_tuple,
+    )(tokens)
+}
+
 fn match_foreach(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
         tuple((
             match_comment,
+            match_hints,
             preceded(
                 TokenKind::Foreach,
-                separated_list1(TokenKind::Comma, match_name),
+                separated_list1(TokenKind::Comma, match_var_pattern),
             ),
             preceded(
                 TokenKind::In,
@@ -155,14 +590,14 @@ fn match_if(tokens: &[Token]) -> ParseResult<StmtNode> {
     let match_if = tuple((
         preceded(
             TokenKind::If,
-            match_expr,
+            recover_expr(match_expr, "`if` condition", &[TokenKind::Colon]),
         ),
         match_indented_block,
     ));
     let match_elif = tuple((
         preceded(
             TokenKind::Elif,
-            match_expr,
+            recover_expr(match_expr, "`elif` condition", &[TokenKind::Colon]),
         ),
         match_indented_block,
     ));
@@ -174,6 +609,7 @@ fn match_if(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
         tuple((
             match_comment,
+            match_hints,
             match_if,
             many0(match_elif),
             opt(match_else),   
@@ -182,47 +618,218 @@ fn match_if(tokens: &[Token]) -> ParseResult<StmtNode> {
     )(tokens)
 }
 
-fn match_assignment(tokens: &[Token]) -> ParseResult<StmtNode> {
-    map_into(
-        terminated(
+fn match_match(tokens: &[Token]) -> ParseResult<StmtNode> {
+    // Empty lines between the cases are allowed, but aren't kept
+    let match_case = preceded(
+        many0(TokenKind::Newline),
+        map(
             tuple((
                 match_comment,
-                match_expr,
-                TokenKind::Assign,
-                match_expr,
+                preceded(TokenKind::Case, match_type),
+                opt(preceded(TokenKind::As, match_name)),
+                match_indented_block,
             )),
+            MatchCase::from_tuple,
+        ),
+    );
+    let match_else = preceded(
+        pair(many0(TokenKind::Newline), TokenKind::Else),
+        match_indented_block,
+    );
+
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            delimited(
+                TokenKind::Match,
+                recover_expr(match_expr, "`match` value", &[TokenKind::Colon]),
+                tuple((TokenKind::Colon, TokenKind::Newline, TokenKind::Indent)),
+            ),
+            many1(match_case),
+            terminated(opt(match_else), TokenKind::Unindent),
+        )),
+        MatchStmt::from_tuple,
+    )(tokens)
+}
+
+fn match_try(tokens: &[Token]) -> ParseResult<StmtNode> {
+    let match_catch = map(
+        tuple((
+            preceded(TokenKind::Catch, match_type),
+            opt(preceded(TokenKind::As, match_name)),
+            match_indented_block,
+        )),
+        CatchClause::from_tuple,
+    );
+
+    let (rest, (comment, hints, try_token, body)) = tuple((
+        match_comment,
+        match_hints,
+        TokenKind::Try,
+        match_indented_block,
+    ))(tokens)?;
+    let (rest, catch_clauses) = many1(match_catch)(rest).map_err(|err| match err {
+        nom::Err::Error(_) => err_failure(
+            ErrorCode::TryWithoutCatch,
+            Some(try_token.span()),
+            "A 'try' block must be followed by at least one 'catch' clause",
+        ),
+        other => other,
+    })?;
+    Ok((rest, TryStmt{
+        comment: comment,
+        hints: hints,
+        body: body,
+        catch_clauses: catch_clauses,
+    }.into()))
+}
+
+fn match_var_decl(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            preceded(TokenKind::Var, match_var_pattern),
+            opt(preceded(TokenKind::Colon, match_type)),
+            preceded(TokenKind::Assign, match_stmt_value),
+        )),
+        VarDeclStmt::from_tuple,
+    )(tokens)
+}
+
+fn match_var_pattern(tokens: &[Token]) -> ParseResult<VarPattern> {
+    alt((
+        map(match_name, VarPattern::Name),
+        map(
+            delimited(
+                TokenKind::LParen,
+                separated_list1(TokenKind::Comma, match_var_pattern),
+                TokenKind::RParen,
+            ),
+            |mut items| {
+                // There are no 1-element tuples, so '(a)' is just a parenthesized name
+                if items.len() == 1 {
+                    items.remove(0)
+                } else {
+                    VarPattern::Tuple(items)
+                }
+            },
+        ),
+    ))(tokens)
+}
+
+fn match_assignment(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            match_expr,
+            match_assignment_op,
+            match_stmt_value,
+        )),
+        AssignmentStmt::from_tuple,
+    )(tokens)
+}
+
+// The value ending a statement, along with the newline that follows it.
+// Block lambdas end with their block instead.
+fn match_stmt_value(tokens: &[Token]) -> ParseResult<ExprNode> {
+    alt((
+        match_block_lambda,
+        terminated(
+            recover_expr(match_expr, "expression", &[TokenKind::Newline]),
             TokenKind::Newline,
         ),
-        |(comment, left, _, right)| {
-            AssignmentStmt::from_tuple((comment, left, right))
+    ))(tokens)
+}
+
+fn match_assignment_op(tokens: &[Token]) -> ParseResult<Option<InfixOp>> {
+    let (rest, token) = get_next(tokens, "assignment op")?;
+    let op = match &token.kind {
+        TokenKind::Assign => None,
+        TokenKind::PlusAssign => Some(InfixOp::Addition),
+        TokenKind::MinusAssign => Some(InfixOp::Subtraction),
+        TokenKind::MultiplyAssign => Some(InfixOp::Multiplication),
+        TokenKind::DivideAssign => Some(InfixOp::Division),
+        TokenKind::PercentAssign => Some(InfixOp::Modulus),
+        TokenKind::PipeAssign => Some(InfixOp::BitwiseOr),
+        TokenKind::CaretAssign => Some(InfixOp::BitwiseXor),
+        TokenKind::AmpersandAssign => Some(InfixOp::BitwiseAnd),
+        TokenKind::ShiftLeftAssign => Some(InfixOp::BitwiseShiftLeft),
+        TokenKind::ShiftRightAssign => Some(InfixOp::BitwiseShiftRight),
+        _ => {
+            return Err(err_bad_match("assignment operator", token));
         },
+    };
+    Ok((rest, op))
+}
+
+fn match_break(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            terminated(map(TokenKind::Break, Token::span), TokenKind::Newline),
+        )),
+        BreakStmt::from_tuple,
+    )(tokens)
+}
+
+fn match_continue(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            terminated(map(TokenKind::Continue, Token::span), TokenKind::Newline),
+        )),
+        ContinueStmt::from_tuple,
     )(tokens)
 }
 
 fn match_return(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
-        pair(
+        tuple((
             match_comment,
-            delimited(TokenKind::Return, opt(match_expr), TokenKind::Newline),
-        ),
+            match_hints,
+            preceded(
+                TokenKind::Return,
+                alt((
+                    value(None, TokenKind::Newline),
+                    map(match_stmt_value, Some),
+                )),
+            ),
+        )),
         ReturnStmt::from_tuple,
     )(tokens)
 }
 
 fn match_panic(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
-        pair(
+        tuple((
             match_comment,
-            delimited(TokenKind::Panic, opt(match_expr), TokenKind::Newline),
-        ),
-        ReturnStmt::from_tuple,
+            match_hints,
+            delimited(TokenKind::Panic, match_expr, TokenKind::Newline),
+        )),
+        PanicStmt::from_tuple,
+    )(tokens)
+}
+
+fn match_throw(tokens: &[Token]) -> ParseResult<StmtNode> {
+    map_into(
+        tuple((
+            match_comment,
+            match_hints,
+            delimited(TokenKind::Throw, match_expr, TokenKind::Newline),
+        )),
+        ThrowStmt::from_tuple,
     )(tokens)
 }
 
 fn match_line(tokens: &[Token]) -> ParseResult<StmtNode> {
     map_into(
         terminated(
-            pair(match_comment, match_expr),
+            tuple((match_comment, match_hints, match_expr)),
             TokenKind::Newline,
         ),
         LineStmt::from_tuple,
@@ -256,4 +863,30 @@ fn match_comment(tokens: &[Token]) -> ParseResult<Comment> {
         ),
         Comment::new,
     )(tokens)
-}
\ No newline at end of file
+}
+
+// Hints must come after any comments attached to the same statement. Any
+// comment lines between a hint and its statement are skipped.
+fn match_hints(tokens: &[Token]) -> ParseResult<Vec<Hint>> {
+    many0(
+        terminated(
+            map(
+                tuple((
+                    preceded(TokenKind::Hint, match_name),
+                    preceded(TokenKind::Colon, match_name),
+                    opt_or(
+                        delimited_list(
+                            TokenKind::LParen,
+                            TokenKind::Comma,
+                            match_expr,
+                            TokenKind::RParen,
+                        ),
+                        Option::unwrap_or_default,
+                    ),
+                )),
+                Hint::from_tuple,
+            ),
+            pair(TokenKind::Newline, match_comment),
+        ),
+    )(tokens)
+}
@@ -1,20 +1,19 @@
 use nom::branch::alt;
-use nom::combinator::{map, map_opt, value};
-use nom::multi::{many0, separated_list1};
-use nom::sequence::{pair, preceded, terminated, tuple};
+use nom::combinator::{map_opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, preceded, tuple};
 use struple::Struple;
 use crate::ast::types::*;
-use crate::ast::primitives::*;
 
 use super::core::*;
 use super::combinators::*;
-use super::parse_expr::match_name;
+use super::parse_expr::{match_identifier, match_name};
 
 pub fn match_type(tokens: &[Token]) -> ParseResult<TypeNode> {
     map_opt(
         separated_list1(
             TokenKind::Pipe,
-            match_primary_type,
+            expecting("type", match_primary_type),
         ),
         |mut types| {
             if types.len() > 1 {
@@ -35,16 +34,10 @@ fn match_primary_type(tokens: &[Token]) -> ParseResult<TypeNode> {
     ))(tokens)
 }
 
-fn match_reference_type(tokens: &[Token]) -> ParseResult<TypeNode> {
+pub fn match_reference_type(tokens: &[Token]) -> ParseResult<TypeNode> {
     map_into(
         pair(
-            map(
-                pair(
-                    many0(terminated(match_name, TokenKind::Dot)),
-                    match_name,
-                ),
-                Identifier::from_tuple,
-            ),
+            match_identifier,
             optional_delimited_list(
                 TokenKind::LSquare,
                 TokenKind::Comma,
@@ -80,12 +73,30 @@ fn match_func_type(tokens: &[Token]) -> ParseResult<TypeNode> {
                     ),
                     |o| o.unwrap_or(TypeNode::Unit),
                 ),
+                match_throws,
             )),
         ),
         FuncType::from_tuple,
     )(tokens)
 }
 
+// The throws clause is parenthesized so that it's unambiguous wherever
+// a function type appears, such as inside a parameter list.
+pub fn match_throws(tokens: &[Token]) -> ParseResult<Vec<TypeNode>> {
+    opt_or(
+        preceded(
+            TokenKind::Throws,
+            delimited_list(
+                TokenKind::LParen,
+                TokenKind::Comma,
+                match_type,
+                TokenKind::RParen,
+            ),
+        ),
+        Option::unwrap_or_default,
+    )(tokens)
+}
+
 fn match_tuple_type(tokens: &[Token]) -> ParseResult<TypeNode> {
     map_into(
         delimited_list(
@@ -195,6 +206,7 @@ mod tests {
                 param_types: Vec::new(),
                 typevars: Vec::new(),
                 return_type: TypeNode::Unit,
+                throws: Vec::new(),
             }.into(),
         )
     }
@@ -214,6 +226,7 @@ mod tests {
                 param_types: Vec::new(),
                 typevars: Vec::new(),
                 return_type: TypeNode::Unit,
+                throws: Vec::new(),
             }.into(),
         )
     }
@@ -241,6 +254,7 @@ mod tests {
                 ],
                 typevars: Vec::new(),
                 return_type: TypeNode::Empty,
+                throws: Vec::new(),
             }.into(),
         )
     }
@@ -274,6 +288,38 @@ mod tests {
                     "T2".into(),
                 ],
                 return_type: TypeNode::Unit,
+                throws: Vec::new(),
+            }.into(),
+        )
+    }
+
+    #[test]
+    fn test_func_with_throws() -> Result<(), nom::Err<ParserError>> {
+        let token_kinds = vec![
+            TokenKind::Fn,
+            TokenKind::LParen,
+            TokenKind::RParen,
+            TokenKind::Arrow,
+            atom("A"),
+            TokenKind::Throws,
+            TokenKind::LParen,
+            atom("E1"),
+            TokenKind::Comma,
+            atom("E2"),
+            TokenKind::RParen,
+        ];
+
+        parser_test(
+            match_func_type,
+            &generate_positions(&token_kinds),
+            FuncType{
+                param_types: Vec::new(),
+                typevars: Vec::new(),
+                return_type: ref_type_basic("A"),
+                throws: vec![
+                    ref_type_basic("E1"),
+                    ref_type_basic("E2"),
+                ],
             }.into(),
         )
     }
@@ -316,8 +362,11 @@ mod tests {
                         ],
                         typevars: Vec::new(),
                         return_type: TypeNode::Unit,
+                        throws: Vec::new(),
                     }.into(),
+                    throws: Vec::new(),
                 }.into(),
+                throws: Vec::new(),
             }.into(),
         )
     }
@@ -402,6 +451,7 @@ mod tests {
                         ],
                         typevars: Vec::new(),
                         return_type: TypeNode::Unit,
+                        throws: Vec::new(),
                     }.into(),
                     TupleType{
                         items: vec![
@@ -0,0 +1,245 @@
+use nom::combinator::peek;
+use nom::sequence::terminated;
+
+use crate::ast::*;
+use crate::ast::visit::Visitor;
+use crate::diagnostics::{ErrorCode, Fix};
+
+use super::core::*;
+
+// Runs the statement parser. If it fails, skips ahead to where the next
+// statement should start and returns an error node in place of the
+// statement. This still fails if the current block has already ended,
+// since there's nothing left to skip.
+pub fn recover_stmt<'a, P>(parser: P) -> impl FnMut(&'a [Token]) -> ParseResult<'a, StmtNode>
+where
+    P: nom::Parser<&'a [Token], StmtNode, ParserError>,
+{
+    recover_member(parser, |error| LineStmt{
+        comment: Comment::empty(),
+        hints: Vec::new(),
+        expr: error.into(),
+    }.into())
+}
+
+// Like 'recover_stmt', but for the members of a class or interface, which
+// aren't statements. 'make_error' wraps the error node in a member.
+pub fn recover_member<'a, O, P>(mut parser: P, make_error: fn(ErrorExpr) -> O) -> impl FnMut(&'a [Token]) -> ParseResult<'a, O>
+where
+    P: nom::Parser<&'a [Token], O, ParserError>,
+{
+    move |tokens: &'a [Token]| {
+        let err = match track_failures(&mut parser, tokens, Some) {
+            (Err(nom::Err::Error(err)), furthest) => pick_furthest(err, furthest),
+            (Err(nom::Err::Failure(err)), _) => err,
+            (other, _) => return other,
+        };
+        match tokens.first() {
+            Some(token) if token.kind != TokenKind::Unindent => {},
+            _ => return Err(nom::Err::Error(err)),
+        }
+        // Comments on the lines above a statement are part of it, so we
+        // skip them too, rather than trying the same statement again.
+        let mut start = tokens;
+        while let [comment, newline, after @ ..] = start {
+            if !matches!(comment.kind, TokenKind::Comment(_)) || newline.kind != TokenKind::Newline {
+                break;
+            }
+            start = after;
+        }
+        let rest = skip_to_next_stmt(start);
+        let skipped = tokens.get(..tokens.len() - rest.len()).unwrap_or_default();
+        let error = ErrorExpr{
+            code: err.code,
+            span: err.span.or_else(|| span_of(skipped)).unwrap_or_else(|| skipped_span(tokens)),
+            message: err.message,
+            fix: err.fix,
+        };
+        Ok((rest, make_error(error)))
+    }
+}
+
+// Parses an expression that must be followed by one of the terminators.
+// If that fails, skips ahead to the next terminator and returns an error
+// node instead. This should only be used once the enclosing statement is
+// certain, such as after the keyword starting it. 'what' describes the
+// expression, for use in error messages.
+pub fn recover_expr<'a, P>(
+    parser: P,
+    what: &'static str,
+    terminators: &'static [TokenKind],
+) -> impl FnMut(&'a [Token]) -> ParseResult<'a, ExprNode>
+where
+    P: nom::Parser<&'a [Token], ExprNode, ParserError>,
+{
+    recover_with(parser, what, terminators, |err, span| ErrorExpr{code: err.code, message: err.message, span, fix: err.fix}.into())
+}
+
+// Like 'recover_expr', but for types.
+pub fn recover_type<'a, P>(
+    parser: P,
+    what: &'static str,
+    terminators: &'static [TokenKind],
+) -> impl FnMut(&'a [Token]) -> ParseResult<'a, TypeNode>
+where
+    P: nom::Parser<&'a [Token], TypeNode, ParserError>,
+{
+    recover_with(parser, what, terminators, |err, span| ErrorType{code: err.code, message: err.message, span, fix: err.fix}.into())
+}
+
+fn recover_with<'a, O, P>(
+    parser: P,
+    what: &'static str,
+    terminators: &'static [TokenKind],
+    make_error: fn(ParserError, (Position, Position)) -> O,
+) -> impl FnMut(&'a [Token]) -> ParseResult<'a, O>
+where
+    P: nom::Parser<&'a [Token], O, ParserError>,
+{
+    let mut parser = terminated(parser, peek(match_any_of(terminators, what)));
+    move |tokens: &'a [Token]| {
+        let err = match track_failures(&mut parser, tokens, Some) {
+            (Err(nom::Err::Error(err)), furthest) => pick_furthest(err, furthest),
+            (other, _) => return other,
+        };
+        if tokens.is_empty() {
+            return Err(nom::Err::Error(err));
+        }
+        let rest = skip_to_terminator(tokens, terminators);
+        let skipped = tokens.get(..tokens.len() - rest.len()).unwrap_or_default();
+        let span = err.span.or_else(|| span_of(skipped)).unwrap_or_else(|| skipped_span(tokens));
+        Ok((rest, make_error(err, span)))
+    }
+}
+
+fn match_any_of<'a>(kinds: &'static [TokenKind], after: &'static str) -> impl FnMut(&'a [Token]) -> ParseResult<'a, &'a Token> {
+    move |tokens: &'a [Token]| {
+        let (rest, token) = get_next(tokens, "terminator")?;
+        if kinds.contains(&token.kind) {
+            Ok((rest, token))
+        } else {
+            Err(err_bad_match_after(kinds, after, tokens))
+        }
+    }
+}
+
+// The error a parser returns is often from an earlier alternative than
+// the one that got furthest, so we report the furthest failure instead.
+fn pick_furthest(err: ParserError, furthest: Option<ParserError>) -> ParserError {
+    match furthest {
+        Some(furthest) => err.merge(furthest),
+        None => err,
+    }
+}
+
+// Skips the rest of the current line, along with the indented block
+// following it if there is one.
+fn skip_to_next_stmt(tokens: &[Token]) -> &[Token] {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate() {
+        let next = i + 1;
+        match token.kind {
+            TokenKind::Indent => depth += 1,
+            TokenKind::Unindent if depth == 0 => return tokens.get(i..).unwrap_or_default(),
+            TokenKind::Unindent => {
+                depth -= 1;
+                if depth == 0 {
+                    return tokens.get(next..).unwrap_or_default();
+                }
+            },
+            TokenKind::Newline if depth == 0 => {
+                let starts_block = tokens.get(next).is_some_and(|t| t.kind == TokenKind::Indent);
+                if !starts_block {
+                    return tokens.get(next..).unwrap_or_default();
+                }
+            },
+            _ => {},
+        }
+    }
+    &[]
+}
+
+// Skips up to (but not including) the first terminator outside of any
+// brackets, stopping early at the end of the line.
+fn skip_to_terminator<'a>(tokens: &'a [Token], terminators: &[TokenKind]) -> &'a [Token] {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate() {
+        let at_end = match token.kind {
+            TokenKind::Newline | TokenKind::Indent | TokenKind::Unindent => true,
+            ref kind if depth == 0 && terminators.contains(kind) => true,
+            TokenKind::LParen | TokenKind::LSquare | TokenKind::LBrace => {
+                depth += 1;
+                false
+            },
+            TokenKind::RParen | TokenKind::RSquare | TokenKind::RBrace => {
+                depth = depth.saturating_sub(1);
+                false
+            },
+            _ => false,
+        };
+        if at_end {
+            return tokens.get(i..).unwrap_or_default();
+        }
+    }
+    &[]
+}
+
+fn span_of(tokens: &[Token]) -> Option<(Position, Position)> {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => Some((first.position, last.end)),
+        _ => None,
+    }
+}
+
+// Nothing was skipped, so we point at wherever we stopped instead.
+fn skipped_span(tokens: &[Token]) -> (Position, Position) {
+    tokens.first().map_or((Position::start(), Position::start()), Token::span)
+}
+
+// Gathers the errors recorded in the error nodes of a program, in the
+// order they appear.
+pub fn collect_errors(program: &Program) -> Vec<ParserError> {
+    let mut collector = ErrorCollector{errors: Vec::new()};
+    collector.visit_block(&program.body);
+    // Errors in class members are kept apart from the other members, so
+    // they aren't visited in order.
+    collector.errors.sort_by_key(|err| err.span);
+    collector.errors
+}
+
+struct ErrorCollector {
+    errors: Vec<ParserError>,
+}
+
+impl ErrorCollector {
+    fn record(&mut self, code: ErrorCode, message: &str, span: (Position, Position), fix: Option<&Fix>) {
+        self.errors.push(ParserError{
+            code,
+            span: Some(span),
+            message: message.to_owned(),
+            source: None,
+            expected: None,
+            fix: fix.cloned().map(Box::new),
+        });
+    }
+}
+
+impl Visitor for ErrorCollector {
+    fn visit_member_error(&mut self, e: &ErrorExpr) {
+        self.record(e.code, &e.message, e.span, e.fix.as_deref());
+    }
+
+    fn visit_expr(&mut self, expr: &ExprNode) {
+        match expr {
+            ExprNode::Error(e) => self.record(e.code, &e.message, e.span, e.fix.as_deref()),
+            _ => self.walk_expr(expr),
+        }
+    }
+
+    fn visit_type(&mut self, typ: &TypeNode) {
+        match typ {
+            TypeNode::Error(t) => self.record(t.code, &t.message, t.span, t.fix.as_deref()),
+            _ => self.walk_type(typ),
+        }
+    }
+}
@@ -26,7 +26,8 @@ pub fn generate_positions(kinds: &[TokenKind]) -> Vec<Token> {
         .map(|(i, token_kind)| {
             Token{
                 kind: token_kind.clone(),
-                position: Position::new(1, 0, i),
+                position: Position::new(1, i, i),
+                end: Position::new(1, i + 1, i + 1),
             }
         })
         .collect()
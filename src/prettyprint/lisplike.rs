@@ -31,8 +31,8 @@ fn prettyprint_stmt(stmt: &StmtNode) -> String {
         })
     }
 
-    fn with_comment<'a>(c: &'a Comment, writer: Writer<'a>) -> Writer<'a> {
-        sequence(vec![comment(c), writer])
+    fn with_metadata<'a>(c: &'a Comment, h: &'a [Hint], writer: Writer<'a>) -> Writer<'a> {
+        sequence(vec![comment(c), hints(h), writer])
     }
 
     fn empty<'a>() -> Writer<'a> {
@@ -58,6 +58,23 @@ fn prettyprint_stmt(stmt: &StmtNode) -> String {
         )
     }
 
+    fn hints(hints: &[Hint]) -> Writer {
+        sequence(
+            hints
+                .iter()
+                .map(|hint| {
+                    format!(
+                        "!{}: {}({})",
+                        hint.target,
+                        hint.name,
+                        hint.args.iter().map(prettyprint_expr).join(", "),
+                    )
+                })
+                .map(literal)
+                .collect(),
+        )
+    }
+
     fn bare_block<'a>(name: &'a str, body: Writer<'a>) -> Writer<'a> {
         Box::new(move |lines, level| {
             lines.push(with_indent(level, format!("({}", name)));
@@ -86,6 +103,30 @@ fn prettyprint_stmt(stmt: &StmtNode) -> String {
         })
     }
 
+    // Block lambdas can only be the value of a statement, and are
+    // written with their body indented on the following lines.
+    fn line_with_value<'a>(
+        prefix: &'static str,
+        mut parts: Vec<String>,
+        value: &'a ExprNode,
+    ) -> Writer<'a> {
+        if let ExprNode::Lambda(e) = value {
+            if let LambdaBody::Block(body) = &e.body {
+                parts.push(prettyprint_lambda_header(e));
+                return Box::new(move |lines, level| {
+                    lines.push(with_indent(
+                        level,
+                        format!("({} {}", prefix, parts.join(" ")),
+                    ));
+                    write_block(body)(lines, level + 4);
+                    lines.push(with_indent(level, "))"));
+                });
+            }
+        }
+        parts.push(prettyprint_expr(value));
+        line(prefix, parts)
+    }
+
     fn write_block(block: &[StmtNode]) -> Writer {
         Box::new(move |lines, level| {
             for stmt in block {
@@ -107,46 +148,124 @@ fn prettyprint_stmt(stmt: &StmtNode) -> String {
         )
     }
 
+    fn write_func_signature(f: &FuncSignatureDefStmt) -> Writer {
+        with_metadata(
+            &f.comment,
+            &f.hints,
+            literal(format!("(fn {})", function_header(f))),
+        )
+    }
+
+    fn write_func_implementation(f: &FuncImplementationDefStmt) -> Writer {
+        with_metadata(
+            &f.function.comment,
+            &f.function.hints,
+            expr_block(
+                "fn",
+                vec![function_header(&f.function)],
+                write_block(&f.body),
+            ),
+        )
+    }
+
+    fn write_const(c: &ConstDefStmt) -> Writer {
+        let mut parts = vec![c.name.to_string()];
+        if let Some(typ) = &c.typ {
+            parts.push(format!("(type {})", prettyprint_type(typ)));
+        }
+        with_metadata(
+            &c.comment,
+            &c.hints,
+            line_with_value("const", parts, &c.value),
+        )
+    }
+
+    fn write_field(f: &FieldSignatureDefStmt) -> Writer {
+        with_metadata(
+            &f.comment,
+            &f.hints,
+            line(
+                "field",
+                vec![f.identifier.to_string(), prettyprint_type(&f.typ)],
+            ),
+        )
+    }
+
+    fn write_error(e: &ErrorExpr) -> Writer<'_> {
+        literal(format!("(error {})", quote(&e.message)))
+    }
+
     fn write_stmt(stmt: &StmtNode) -> Writer {
         match stmt {
             StmtNode::Program(s) => write_block(&s.body),
-            StmtNode::Import(s) => with_comment(
+            StmtNode::Import(s) => with_metadata(
                 &s.comment,
+                &s.hints,
                 bare_block(
                     "import",
                     pair(
                         literal(s.source.to_string()),
-                        literal(s.imports.iter().map(quote).join(" ")),
+                        literal(
+                            s.imports
+                                .iter()
+                                .map(|i| match &i.alias {
+                                    Some(alias) => {
+                                        format!("(as {} {})", quote(&i.name), quote(alias))
+                                    }
+                                    None => quote(&i.name),
+                                })
+                                .join(" "),
+                        ),
                     ),
                 ),
             ),
-            StmtNode::InterfaceDef(s) => with_comment(
-                &s.comment,
-                bare_block("interface", sequence(vec![literal("WIP".to_owned())])),
-            ),
-            StmtNode::ClassDef(s) => with_comment(
-                &s.comment,
-                bare_block("class", sequence(vec![literal("WIP".to_owned())])),
-            ),
-            StmtNode::SentinalDef(s) => with_comment(
-                &s.comment,
-                bare_block("sentinal", sequence(vec![literal("WIP".to_owned())])),
-            ),
-            StmtNode::FieldSignatureDef(s) => with_comment(
-                &s.comment,
-                bare_block("field", sequence(vec![literal("WIP".to_owned())])),
-            ),
-            StmtNode::FuncSignatureDef(s) => {
-                with_comment(&s.comment, literal(format!("(fn {})", function_header(s))))
+            StmtNode::InterfaceDef(s) => {
+                let header = vec![format!(
+                    "{}{}",
+                    s.identifier,
+                    bracket_if_exists(s.typevars.iter().join(", ")),
+                )];
+                let mut writers: Vec<Writer> = s.fields.iter().map(write_field).collect();
+                writers.extend(s.functions.iter().map(write_func_signature));
+                writers.extend(s.errors.iter().map(write_error));
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    expr_block("interface", header, sequence(writers)),
+                )
             }
-            StmtNode::FuncImplementationDef(s) => with_comment(
-                &s.function.comment,
-                expr_block(
-                    "fn",
-                    vec![function_header(&s.function)],
-                    write_block(&s.body),
-                ),
+            StmtNode::ClassDef(s) => {
+                let mut header = vec![format!(
+                    "{}{}",
+                    s.identifier,
+                    bracket_if_exists(s.typevars.iter().join(", ")),
+                )];
+                if !s.implements.is_empty() {
+                    header.push(format!(
+                        "(implements {})",
+                        s.implements.iter().map(prettyprint_type).join(" "),
+                    ));
+                }
+                let mut writers: Vec<Writer> = s.constants.iter().map(write_const).collect();
+                writers.extend(s.fields.iter().map(write_field));
+                writers.extend(s.constructor.iter().map(write_func_implementation));
+                writers.extend(s.functions.iter().map(write_func_implementation));
+                writers.extend(s.errors.iter().map(write_error));
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    expr_block("class", header, sequence(writers)),
+                )
+            }
+            StmtNode::SentinalDef(s) => with_metadata(
+                &s.comment,
+                &s.hints,
+                line("sentinal", vec![s.identifier.to_string()]),
             ),
+            StmtNode::ConstDef(s) => write_const(s),
+            StmtNode::FieldSignatureDef(s) => write_field(s),
+            StmtNode::FuncSignatureDef(s) => write_func_signature(s),
+            StmtNode::FuncImplementationDef(s) => write_func_implementation(s),
             StmtNode::If(s) => {
                 let mut writers = vec![expr_block(
                     "if-branch",
@@ -165,52 +284,134 @@ fn prettyprint_stmt(stmt: &StmtNode) -> String {
                 }
                 sequence(vec![
                     comment(&s.comment),
+                    hints(&s.hints),
                     bare_block("if", sequence(writers)),
                 ])
             }
-            StmtNode::For(s) => with_comment(
-                &s.comment,
-                bare_block("for", sequence(vec![literal("WIP".to_owned())])),
-            ),
-            StmtNode::Foreach(s) => with_comment(
+            StmtNode::Match(s) => {
+                let mut writers = Vec::new();
+                for case in &s.cases {
+                    let mut header = vec![prettyprint_type(&case.typ)];
+                    if let Some(name) = &case.name {
+                        header.push(name.to_string());
+                    }
+                    writers.push(comment(&case.comment));
+                    writers.push(expr_block("case", header, write_block(&case.body)));
+                }
+                if let Some(body) = &s.else_case {
+                    writers.push(bare_block("else-case", write_block(body)));
+                }
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    expr_block("match", vec![prettyprint_expr(&s.value)], sequence(writers)),
+                )
+            }
+            StmtNode::Try(s) => {
+                let mut writers = vec![bare_block("try-body", write_block(&s.body))];
+                for clause in &s.catch_clauses {
+                    let mut header = vec![prettyprint_type(&clause.typ)];
+                    if let Some(name) = &clause.name {
+                        header.push(name.to_string());
+                    }
+                    writers.push(expr_block("catch", header, write_block(&clause.body)));
+                }
+                with_metadata(&s.comment, &s.hints, bare_block("try", sequence(writers)))
+            }
+            StmtNode::For(s) => {
+                let mut header = vec![
+                    s.variable.to_string(),
+                    format!("(from {})", prettyprint_expr(&s.start)),
+                    match s.direction {
+                        ForDirection::Ascending => format!("(to {})", prettyprint_expr(&s.end)),
+                        ForDirection::Descending => {
+                            format!("(downto {})", prettyprint_expr(&s.end))
+                        }
+                    },
+                ];
+                if let Some(step) = &s.step {
+                    header.push(format!("(by {})", prettyprint_expr(step)));
+                }
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    expr_block("for", header, write_block(&s.body)),
+                )
+            }
+            StmtNode::Foreach(s) => with_metadata(
                 &s.comment,
+                &s.hints,
                 expr_block(
                     "foreach",
                     vec![
-                        format!("(vars {})", &s.variables.iter().join(" ")),
+                        format!(
+                            "(vars {})",
+                            &s.variables.iter().map(prettyprint_var_pattern).join(" ")
+                        ),
                         prettyprint_expr(&s.iterable),
                     ],
                     write_block(&s.body),
                 ),
             ),
-            StmtNode::While(s) => with_comment(
+            StmtNode::While(s) => with_metadata(
                 &s.comment,
+                &s.hints,
                 expr_block(
                     "while",
                     vec![prettyprint_expr(&s.cond)],
                     write_block(&s.body),
                 ),
             ),
-            StmtNode::Return(s) => with_comment(
+            StmtNode::Break(s) => with_metadata(&s.comment, &s.hints, literal("break".to_owned())),
+            StmtNode::Continue(s) => {
+                with_metadata(&s.comment, &s.hints, literal("continue".to_owned()))
+            }
+            StmtNode::Return(s) => with_metadata(
                 &s.comment,
+                &s.hints,
                 match s.value {
-                    Some(ref e) => line("return", vec![prettyprint_expr(e)]),
+                    Some(ref e) => line_with_value("return", Vec::new(), e),
                     None => literal("return".to_owned()),
                 },
             ),
-            StmtNode::Panic(s) => {
-                with_comment(&s.comment, line("panic", vec![prettyprint_expr(&s.value)]))
-            }
-            StmtNode::Assignment(s) => with_comment(
+            StmtNode::Panic(s) => with_metadata(
                 &s.comment,
-                line(
-                    "assign",
-                    vec![prettyprint_expr(&s.target), prettyprint_expr(&s.value)],
-                ),
+                &s.hints,
+                line("panic", vec![prettyprint_expr(&s.value)]),
+            ),
+            StmtNode::Throw(s) => with_metadata(
+                &s.comment,
+                &s.hints,
+                line("throw", vec![prettyprint_expr(&s.value)]),
             ),
-            StmtNode::Line(s) => {
-                with_comment(&s.comment, line("", vec![prettyprint_expr(&s.expr)]))
+            StmtNode::VarDecl(s) => {
+                let mut parts = vec![prettyprint_var_pattern(&s.target)];
+                if let Some(typ) = &s.typ {
+                    parts.push(format!("(type {})", prettyprint_type(typ)));
+                }
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    line_with_value("var", parts, &s.value),
+                )
             }
+            StmtNode::Assignment(s) => {
+                let mut parts = Vec::new();
+                if let Some(op) = &s.op {
+                    parts.push(format!("{}=", op.to_symbol()));
+                }
+                parts.push(prettyprint_expr(&s.target));
+                with_metadata(
+                    &s.comment,
+                    &s.hints,
+                    line_with_value("assign", parts, &s.value),
+                )
+            }
+            StmtNode::Line(s) => with_metadata(
+                &s.comment,
+                &s.hints,
+                line("", vec![prettyprint_expr(&s.expr)]),
+            ),
             StmtNode::EmptyLine() => empty(),
         }
     }
@@ -249,6 +450,11 @@ fn prettyprint_expr(expr: &ExprNode) -> String {
             ExprNode::Index(e) => {
                 format!("(index {} {})", print_expr(&e.source), print_expr(&e.index),)
             }
+            ExprNode::TypeApplication(e) => format!(
+                "(type-args {} {})",
+                print_expr(&e.source),
+                e.type_args.iter().map(prettyprint_type).join(" "),
+            ),
             ExprNode::Range(e) => {
                 format!("(range {} {})", print_expr(&e.start), print_expr(&e.end),)
             }
@@ -265,10 +471,24 @@ fn prettyprint_expr(expr: &ExprNode) -> String {
             ExprNode::Variable(e) => e.to_string(),
             ExprNode::Array(e) => format!("(array {})", print_exprs(&e.items),),
             ExprNode::Tuple(e) => format!("(tuple {})", print_exprs(&e.items),),
-            ExprNode::StringLiteral(e) => quote(e.as_ref()),
+            ExprNode::StringLiteral(e) => e.to_string(),
             ExprNode::IntLiteral(e) => e.to_string(),
             ExprNode::FloatLiteral(e) => e.to_string(),
             ExprNode::BoolLiteral(e) => e.to_string(),
+            ExprNode::Lambda(e) => format!(
+                "{} {})",
+                prettyprint_lambda_header(e),
+                match &e.body {
+                    LambdaBody::Expr(body) => print_expr(body),
+                    // Block lambdas are normally written by the statement
+                    // holding them, so this is just a fallback.
+                    LambdaBody::Block(body) => body
+                        .iter()
+                        .map(prettyprint_stmt)
+                        .flat_map(|s| s.lines().map(str::trim).map(str::to_owned).collect_vec())
+                        .join(" "),
+                },
+            ),
             ExprNode::Error(e) => format!("(error {})", quote(&e.message),),
         }
     }
@@ -276,6 +496,35 @@ fn prettyprint_expr(expr: &ExprNode) -> String {
     print_expr(expr)
 }
 
+fn prettyprint_lambda_header(e: &LambdaExpr) -> String {
+    format!(
+        "(lambda {} (type {}) {}",
+        if e.param_names.is_empty() {
+            "(params)".to_owned()
+        } else {
+            format!("(params {})", e.param_names.iter().join(" "))
+        },
+        prettyprint_func_type(&e.signature),
+        if e.captures.is_empty() {
+            "(captures)".to_owned()
+        } else {
+            format!("(captures {})", e.captures.iter().join(" "))
+        },
+    )
+}
+
+fn prettyprint_var_pattern(pattern: &VarPattern) -> String {
+    match pattern {
+        VarPattern::Name(name) => name.to_string(),
+        VarPattern::Tuple(items) => {
+            format!(
+                "(tuple {})",
+                items.iter().map(prettyprint_var_pattern).join(" ")
+            )
+        }
+    }
+}
+
 fn prettyprint_type(typ: &TypeNode) -> String {
     match typ {
         TypeNode::Reference(t) => format!(
@@ -306,13 +555,18 @@ fn bracket_if_exists(s: String) -> String {
 
 fn prettyprint_func_type(f: &FuncType) -> String {
     format!(
-        "fn{}({}){}",
+        "fn{}({}){}{}",
         bracket_if_exists(f.typevars.iter().join(", ")),
         prettyprint_types(&f.param_types),
         if f.return_type == TypeNode::Unit {
             "".to_owned()
         } else {
             format!(" -> {}", prettyprint_type(&f.return_type))
+        },
+        if f.throws.is_empty() {
+            String::new()
+        } else {
+            format!(" throws({})", prettyprint_types(&f.throws))
         }
     )
 }
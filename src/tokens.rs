@@ -34,12 +34,22 @@ pub enum TokenKind {
     ShiftRight,
     Dot,
     Assign,
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    DivideAssign,
+    PercentAssign,
+    PipeAssign,
+    CaretAssign,
+    AmpersandAssign,
+    ShiftLeftAssign,
+    ShiftRightAssign,
     Colon,
     Arrow,
     Comma,
 
     // Literals and identifiers
-    StringLiteral(String),
+    StringLiteral(Box<StringLiteral>),
     IntLiteral(IntLiteral),
     FloatLiteral(FloatLiteral),
     BoolLiteral(bool),
@@ -54,28 +64,45 @@ pub enum TokenKind {
     If,
     Elif,
     Else,
+    Match,
+    Case,
     For,
     From,
     To,
+    DownTo,
+    By,
     Foreach,
     In,
     While,
+    Break,
+    Continue,
     Return,
     Panic,
+    Try,
+    Catch,
+    Throw,
+    Throws,
     Fn,
     Constructor,
     Interface,
     Class,
     Sentinal,
     Const,
+    Var,
     Implements,
+    Import,
+    As,
 
     // Misc
     Comment(String),
+    Hint,
     Indent,
     Unindent,
     Newline,
     EndOfFile,
+    // Replaces text the lexer couldn't make sense of, if it's recovering
+    // from errors.
+    Error,
 }
 
 impl AsRef<TokenKind> for TokenKind {
@@ -89,43 +116,176 @@ impl TokenKind {
     pub fn name(&self) -> &str {
         self.into()
     }
+
+    // How the token is written in source code, if every token of this
+    // kind is written the same way.
+    #[must_use]
+    pub fn spelling(&self) -> Option<&'static str> {
+        #[allow(clippy::match_same_arms)]
+        let spelling = match self {
+            TokenKind::LParen => "(",
+            TokenKind::RParen => ")",
+            TokenKind::LBrace => "{",
+            TokenKind::RBrace => "}",
+            TokenKind::LSquare => "[",
+            TokenKind::RSquare => "]",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Multiply => "*",
+            TokenKind::Divide => "/",
+            TokenKind::Percent => "%",
+            TokenKind::Equals => "==",
+            TokenKind::NotEquals => "!=",
+            TokenKind::LessThanEquals => "<=",
+            TokenKind::GreaterThanEquals => ">=",
+            TokenKind::LessThan => "<",
+            TokenKind::GreaterThan => ">",
+            TokenKind::Bang => "!",
+            TokenKind::Tilde => "~",
+            TokenKind::Pipe => "|",
+            TokenKind::Caret => "^",
+            TokenKind::Ampersand => "&",
+            TokenKind::ShiftLeft => "<<",
+            TokenKind::ShiftRight => ">>",
+            TokenKind::Dot => ".",
+            TokenKind::Assign => "=",
+            TokenKind::PlusAssign => "+=",
+            TokenKind::MinusAssign => "-=",
+            TokenKind::MultiplyAssign => "*=",
+            TokenKind::DivideAssign => "/=",
+            TokenKind::PercentAssign => "%=",
+            TokenKind::PipeAssign => "|=",
+            TokenKind::CaretAssign => "^=",
+            TokenKind::AmpersandAssign => "&=",
+            TokenKind::ShiftLeftAssign => "<<=",
+            TokenKind::ShiftRightAssign => ">>=",
+            TokenKind::Colon => ":",
+            TokenKind::Arrow => "->",
+            TokenKind::Comma => ",",
+            TokenKind::BoolLiteral(true) => "true",
+            TokenKind::BoolLiteral(false) => "false",
+            TokenKind::InstanceOf => "instanceof",
+            TokenKind::Or => "or",
+            TokenKind::And => "and",
+            TokenKind::If => "if",
+            TokenKind::Elif => "elif",
+            TokenKind::Else => "else",
+            TokenKind::Match => "match",
+            TokenKind::Case => "case",
+            TokenKind::For => "for",
+            TokenKind::From => "from",
+            TokenKind::To => "to",
+            TokenKind::DownTo => "downto",
+            TokenKind::By => "by",
+            TokenKind::Foreach => "foreach",
+            TokenKind::In => "in",
+            TokenKind::While => "while",
+            TokenKind::Break => "break",
+            TokenKind::Continue => "continue",
+            TokenKind::Return => "return",
+            TokenKind::Panic => "panic",
+            TokenKind::Try => "try",
+            TokenKind::Catch => "catch",
+            TokenKind::Throw => "throw",
+            TokenKind::Throws => "throws",
+            TokenKind::Fn => "fn",
+            TokenKind::Constructor => "constructor",
+            TokenKind::Interface => "interface",
+            TokenKind::Class => "class",
+            TokenKind::Sentinal => "sentinal",
+            TokenKind::Const => "const",
+            TokenKind::Var => "var",
+            TokenKind::Implements => "implements",
+            TokenKind::Import => "import",
+            TokenKind::As => "as",
+            TokenKind::Hint => "!",
+            TokenKind::StringLiteral(_)
+            | TokenKind::IntLiteral(_)
+            | TokenKind::FloatLiteral(_)
+            | TokenKind::Atom(_)
+            | TokenKind::Comment(_)
+            | TokenKind::Indent
+            | TokenKind::Unindent
+            | TokenKind::Newline
+            | TokenKind::EndOfFile
+            | TokenKind::Error => return None,
+        };
+        Some(spelling)
+    }
+
+    // A short description of the token, for use in error messages.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        if let Some(spelling) = self.spelling() {
+            return format!("`{spelling}`");
+        }
+        match self {
+            TokenKind::StringLiteral(_) => "string literal".to_owned(),
+            TokenKind::IntLiteral(lit) => format!("`{lit}`"),
+            TokenKind::FloatLiteral(lit) => format!("`{lit}`"),
+            TokenKind::Atom(name) => format!("`{name}`"),
+            TokenKind::Comment(_) => "comment".to_owned(),
+            TokenKind::Indent => "indented block".to_owned(),
+            TokenKind::Unindent => "end of block".to_owned(),
+            TokenKind::Newline => "newline".to_owned(),
+            TokenKind::Error => "invalid token".to_owned(),
+            _ => "end of file".to_owned(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct Position {
     pub lineno: usize,
 
-    // column and offset are w.r.t. characters, not bytes
+    // column is w.r.t. characters, and utf16_column is w.r.t. UTF-16 code
+    // units, which is what editors using the language server protocol
+    // expect. offset is the byte offset into the source.
     pub column: usize,
+    pub utf16_column: usize,
     pub offset: usize,
 }
 
 impl Position {
     #[must_use]
     pub fn start() -> Position {
-        Position{lineno: 0, column: 0, offset: 0}
+        Position{lineno: 0, column: 0, utf16_column: 0, offset: 0}
     }
 
+    // Assumes every char before the position on its line is ASCII, so
+    // all the columns match.
     #[must_use]
     pub fn new(lineno: usize, column: usize, offset: usize) -> Position {
-        Position{lineno, column, offset}
+        Position{lineno, column, utf16_column: column, offset}
     }
 
     pub fn advance(&mut self, c: char) {
-        self.offset += 1;
+        self.offset += c.len_utf8();
         if c == '\n' {
             self.lineno += 1;
             self.column = 0;
+            self.utf16_column = 0;
         } else {
             self.column += 1;
+            self.utf16_column += c.len_utf16();
         }
     }
 
+    // The position just past 'c', if 'c' starts here.
+    #[must_use]
+    pub fn after(&self, c: char) -> Position {
+        let mut end = *self;
+        end.advance(c);
+        end
+    }
+
+    // Moves right by the given number of ASCII chars.
     #[must_use]
     pub fn add_horizontal(&self, col_offset: usize) -> Position {
         Position { 
             lineno: self.lineno,
             column: self.column + col_offset,
+            utf16_column: self.utf16_column + col_offset,
             offset: self.offset + col_offset,
         }
     }
@@ -149,64 +309,19 @@ impl fmt::Display for Position {
     }
 }
 
+// 'position' is where the token starts, and 'end' is just past its last
+// char. Tokens the lexer makes up, like indents and unindents, are empty.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Token {
     pub kind: TokenKind,
     pub position: Position,
+    pub end: Position,
 }
 
 impl Token {
     #[must_use]
     pub fn span(&self) -> (Position, Position) {
-        (self.position, self.end_position())
-    }
-
-    #[must_use]
-    pub fn end_position(&self) -> Position {
-        #[allow(clippy::match_same_arms)]
-        let offset = match &self.kind {
-            TokenKind::LParen => 1,
-            TokenKind::RParen => 1,
-            TokenKind::LBrace => 1,
-            TokenKind::RBrace => 1,
-            TokenKind::LSquare => 1,
-            TokenKind::RSquare => 1,
-            TokenKind::Plus => 1,
-            TokenKind::Minus => 1,
-            TokenKind::Multiply => 1,
-            TokenKind::Divide => 1,
-            TokenKind::Percent => 1,
-            TokenKind::Equals => 2,
-            TokenKind::NotEquals => 2,
-            TokenKind::LessThanEquals => 2,
-            TokenKind::GreaterThanEquals => 2,
-            TokenKind::LessThan => 1,
-            TokenKind::GreaterThan => 1,
-            TokenKind::Bang => 1,
-            TokenKind::Tilde => 1,
-            TokenKind::Pipe => 1,
-            TokenKind::Ampersand => 1,
-            TokenKind::Caret => 1,
-            TokenKind::ShiftLeft => 2,
-            TokenKind::ShiftRight => 2,
-            TokenKind::Dot => 1,
-            TokenKind::Assign => 1,
-            TokenKind::Colon => 1,
-            TokenKind::Arrow => 2,
-            TokenKind::Comma => 1,
-            TokenKind::StringLiteral(s) => s.chars().count(),
-            TokenKind::IntLiteral(lit) => lit.char_len(),
-            TokenKind::FloatLiteral(lit) => lit.char_len(),
-            TokenKind::BoolLiteral(lit) => if *lit { 4 } else { 5 },
-            TokenKind::Atom(atom) => atom.chars().count(),
-            TokenKind::Comment(text) => 2 + text.chars().count(),
-            TokenKind::Indent => 0,
-            TokenKind::Unindent => 0,
-            TokenKind::Newline => 0,
-            TokenKind::EndOfFile => 0,
-            rest => rest.name().chars().count(),
-        };
-        self.position.add_horizontal(offset)
+        (self.position, self.end)
     }
 }
 
@@ -17,11 +17,6 @@ impl IntLiteral {
             raw_value: raw_value,
         })
     }
-
-    #[must_use]
-    pub fn char_len(&self) -> usize {
-        self.digits.chars().count() + if self.base == 10 { 0 } else { 2 }
-    }
 }
 
 impl fmt::Display for IntLiteral {
@@ -48,17 +43,6 @@ pub struct FloatLiteral{
     pub power: Atom,
 }
 
-impl FloatLiteral{
-    #[must_use]
-    pub fn char_len(&self) -> usize {
-        let mut count = self.integral_digits.chars().count() + 1 + self.fractional_digits.chars().count();
-        if !self.power.is_empty() {
-            count += 1 + self.power.chars().count();
-        }
-        count
-    }
-}
-
 impl fmt::Display for FloatLiteral {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.power.is_empty() {
@@ -67,4 +51,42 @@ impl fmt::Display for FloatLiteral {
             write!(f, "{}.{}e{}", self.integral_digits, self.fractional_digits, self.power)
         }
     }
-}
\ No newline at end of file
+}
+
+// 'value' is the string itself, and 'raw' is how it's written between the
+// quotes in the source code, escapes and all. Keeping the original spelling
+// lets us write the literal back out the way the user wrote it.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct StringLiteral {
+    pub value: String,
+    pub raw: String,
+}
+
+// Makes a literal with the value, escaping whatever needs to be escaped.
+impl From<&str> for StringLiteral {
+    fn from(value: &str) -> StringLiteral {
+        let mut raw = String::new();
+        for c in value.chars() {
+            match c {
+                '"' => raw.push_str("\\\""),
+                '\\' => raw.push_str("\\\\"),
+                '\n' => raw.push_str("\\n"),
+                '\r' => raw.push_str("\\r"),
+                '\t' => raw.push_str("\\t"),
+                '\0' => raw.push_str("\\0"),
+                _ if c.is_control() => raw.extend(c.escape_unicode()),
+                _ => raw.push(c),
+            }
+        }
+        StringLiteral{
+            value: value.to_owned(),
+            raw: raw,
+        }
+    }
+}
+
+impl fmt::Display for StringLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.raw)
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
-use impo::{lex, parse};
+use impo::cst::parse_cst;
 use impo::prettyprint::lisplike::prettyprint_program;
 use anyhow::{Context, Result, anyhow};
 use pretty_assertions::assert_eq;
@@ -40,11 +40,24 @@ use nom::sequence::{
 pub type AnyError = anyhow::Error;
 
 pub fn compile(text: impl AsRef<str>) -> Result<String, AnyError> {
-    let tokens = lex(text)
-        .context("Error lexing")?;
-    let ast = parse(&tokens)
-        .context("Error parsing")?;
-    Ok(prettyprint_program(ast))
+    let (output, errors) = compile_with_recovery(text)?;
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")).context("Error parsing"))
+    }
+    Ok(output)
+}
+
+// Like 'compile', but returns the parse tree even if the parser had to
+// recover from errors, along with the errors themselves.
+pub fn compile_with_recovery(text: impl AsRef<str>) -> Result<(String, Vec<String>), AnyError> {
+    let text = text.as_ref();
+    let (cst, lexer_errors) = parse_cst(text);
+    assert_eq!(cst.to_string(), text, "Syntax tree doesn't match the source code");
+    let (ast, parser_errors) = cst.to_ast();
+    let errors = lexer_errors.iter().map(ToString::to_string)
+        .chain(parser_errors.iter().map(ToString::to_string))
+        .collect();
+    Ok((prettyprint_program(ast), errors))
 }
 
 pub fn check(input: impl AsRef<str>, expected_output: impl AsRef<str>) -> Result<(), AnyError> {
@@ -57,6 +57,22 @@ fn test_lookup() -> Result<(), AnyError> {
         "(a.b).c",
         "(lookup (paren (lookup a b)) c)",
     )?;
+    check(
+        "t.0",
+        "(lookup t 0)",
+    )?;
+    check(
+        "t.0.12",
+        "(lookup t 0 12)",
+    )?;
+    check(
+        "a.b.0.1.c",
+        "(lookup (lookup (lookup a b) 0 1) c)",
+    )?;
+    check(
+        "(t.0).1",
+        "(lookup (paren (lookup t 0)) 1)",
+    )?;
 
     Ok(())
 }
@@ -106,8 +122,8 @@ fn test_index() -> Result<(), AnyError> {
         "(index (index (index a b) c) d)",
     )?;
     check(
-        "a[b](c)[d](e)",
-        "(call (index (call (index a b) c) d) e)",
+        "var b = 0\nvar d = 1\na[b](c)[d](e)",
+        "(var b 0)\n(var d 1)\n(call (index (call (index a b) c) d) e)",
     )?;
     check(
         "a[x to y]",
@@ -137,4 +153,86 @@ fn test_tuples() -> Result<(), AnyError> {
     )?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_arrays() -> Result<(), AnyError> {
+    check(
+        "[]",
+        "(array )",
+    )?;
+    check(
+        "[1, 2, 3]",
+        "(array 1 2 3)",
+    )?;
+    check(
+        "[[a], [b + c]][0]",
+        "(index (array (array a) (array (infix b + c))) 0)",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_type_application() -> Result<(), AnyError> {
+    check(
+        "Array[T](8)",
+        "(call (type-args Array T) 8)",
+    )?;
+    check(
+        "copy[Int](a, b)",
+        "(call (type-args copy Int) a b)",
+    )?;
+    check(
+        "a.make[Map[K, V], (Int, Bool)]()",
+        "(call (type-args (lookup a make) Map[K, V] (Int, Bool)))",
+    )?;
+    check(
+        "foreach i in indexes:\n    funcs[i](x)\n",
+        "(foreach (vars i) indexes\n    (call (index funcs i) x)\n)\n",
+    )?;
+    check(
+        "funcs[i](x)",
+        "(call (type-args funcs i) x)",
+    )?;
+    check(
+        "funcs[Key]",
+        "(index funcs Key)",
+    )?;
+    check(
+        "funcs[A + 1](x)",
+        "(call (index funcs (infix A + 1)) x)",
+    )?;
+    check(
+        "from m import MAX, MIN as LOW\nt[MAX](x)\nt[LOW](x)\n",
+        "(import\n    m\n    \"MAX\" (as \"MIN\" \"LOW\")\n)\n(call (index t MAX) x)\n(call (index t LOW) x)",
+    )?;
+    check(
+        "class Foo:\n    const MAX = 8\n    fn get(t: Tuple) -> Int:\n        return t[MAX](1)\n",
+        "(class Foo\n    (const MAX 8)\n    (fn get (params t) (type fn(Tuple) -> Int)\n        (return (call (index t MAX) 1))\n    )\n)\n",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_lambdas() -> Result<(), AnyError> {
+    check(
+        "fn() -> Int: 3",
+        "(lambda (params) (type fn() -> Int) (captures) 3)",
+    )?;
+    check(
+        "fn(x: Int) -> Int: x + offset",
+        "(lambda (params x) (type fn(Int) -> Int) (captures offset) (infix x + offset))",
+    )?;
+    check(
+        "sort(items, fn(a: T, b: T) -> Bool: a.key < b.key)",
+        "(call sort items (lambda (params a b) (type fn(T, T) -> Bool) (captures) (infix (lookup a key) < (lookup b key))))",
+    )?;
+    check(
+        "fn(a: Int) -> fn(Int) -> Int: fn(b: Int) -> Int: a + b + c",
+        "(lambda (params a) (type fn(Int) -> fn(Int) -> Int) (captures c) (lambda (params b) (type fn(Int) -> Int) (captures a c) (infix a + b + c)))",
+    )?;
+
+    Ok(())
+}
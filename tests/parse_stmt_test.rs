@@ -7,23 +7,25 @@ fn test_parsing_statements() -> Result<(), AnyError> {
     check_files(
         project_relative_path("./tests/parse_test_cases"),
         |test| {
-            let outcome = compile(&test.input_source_code);
-            match outcome {
-                Ok(actual) => {
-                    if let Some(ref expected_parse_tree) = test.expected_parse_tree {
-                        assert_str_eq(actual, expected_parse_tree, &test.context);
+            let (actual, errors) = compile_with_recovery(&test.input_source_code)?;
+            if let Some(ref expected_parse_tree) = test.expected_parse_tree {
+                assert_str_eq(&actual, expected_parse_tree, &test.context);
+            }
+            match test.expected_error {
+                Some(ref expected_err) => {
+                    if errors.is_empty() {
+                        return Err(anyhow::anyhow!("Expected an error, but parsed:\n{}", actual))
                     }
+                    assert_str_eq(errors.join("\n"), expected_err, &test.context);
                 },
-                Err(err) => {
-                    if let Some(ref expected_err) = test.expected_output {
-                        assert_str_eq(format!("{:?}", err), expected_err, &test.context);
-                    } else {
-                        return Err(err)
+                None => {
+                    if !errors.is_empty() {
+                        return Err(anyhow::anyhow!("Unexpected errors:\n{}", errors.join("\n")))
                     }
-                }
+                },
             }
-            
+
             Ok(())
         }
     )
-}
\ No newline at end of file
+}